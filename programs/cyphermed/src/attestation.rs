@@ -0,0 +1,293 @@
+use anchor_lang::prelude::*;
+use solana_instructions_sysvar::get_instruction_relative;
+use solana_sdk_ids::ed25519_program;
+use crate::state::{ContentHash, MedicalRecord, ProviderSigningKey};
+use crate::errors::CypherMedError;
+
+/// Size of the Ed25519 program's instruction header (count + padding)
+const ED25519_HEADER_LEN: usize = 2;
+
+/// Size of one `Ed25519SignatureOffsets` entry (seven u16 fields)
+const ED25519_OFFSETS_LEN: usize = 14;
+
+/// Instruction index meaning "data lives in the Ed25519 instruction itself"
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Domain tag keeping key bindings distinct from any other signed message
+const ENCRYPTION_KEY_DOMAIN: &[u8] = b"cyphermed-encryption-key";
+
+/// Bytes a provider signs to attest a record's content at a given version:
+/// record PDA (32) || hash algorithm (1) || digest (32) || version (u32 LE)
+pub fn attestation_message(record: &Pubkey, data_hash: &ContentHash, version: u32) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 + ContentHash::LEN + 4);
+    message.extend_from_slice(record.as_ref());
+    message.push(data_hash.algorithm as u8);
+    message.extend_from_slice(&data_hash.digest);
+    message.extend_from_slice(&version.to_le_bytes());
+    message
+}
+
+/// Bytes a wallet signs to bind an X25519 key to itself:
+/// domain tag || owner (32) || key id (u32 LE) || X25519 public key (32)
+pub fn encryption_key_binding_message(owner: &Pubkey, key_id: u32, public_key: &[u8; 32]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(ENCRYPTION_KEY_DOMAIN.len() + 32 + 4 + 32);
+    message.extend_from_slice(ENCRYPTION_KEY_DOMAIN);
+    message.extend_from_slice(owner.as_ref());
+    message.extend_from_slice(&key_id.to_le_bytes());
+    message.extend_from_slice(public_key);
+    message
+}
+
+/// Mark the record's current version as attested by the provider's
+/// registered signing key; the matching Ed25519 instruction must precede this one
+///
+/// `signing_key` is the provider's signing key PDA. Once a provider has
+/// registered a key every version they write must be attested; providers
+/// without one leave the record unattested.
+pub fn attest_record<'info>(
+    record: &mut Account<'info, MedicalRecord>,
+    signing_key: &AccountInfo<'info>,
+    instructions_sysvar: Option<&UncheckedAccount<'info>>,
+    now: i64,
+) -> Result<()> {
+    let Some(signing_key) = ProviderSigningKey::load(signing_key)? else {
+        return Ok(());
+    };
+    let instructions_sysvar =
+        instructions_sysvar.ok_or(CypherMedError::InstructionsSysvarRequired)?;
+
+    let message = attestation_message(&record.key(), &record.data_hash, record.version);
+    verify_ed25519_attestation(instructions_sysvar, &signing_key.signing_key, &message)?;
+
+    record.attested = true;
+    record.attested_by = Some(signing_key.signing_key);
+
+    emit!(RecordAttestedEvent {
+        record: record.key(),
+        provider: signing_key.provider,
+        signing_key: signing_key.signing_key,
+        version: record.version,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Require that the instruction immediately before the current one is an
+/// Ed25519 program verification of `message` signed by `signing_key`, and
+/// return the verified signature
+///
+/// The Ed25519 program has already checked the signature by the time this
+/// instruction runs, so only its inputs need to be matched here. Exactly one
+/// signature is accepted, with all data carried in that instruction.
+pub fn verify_ed25519_attestation(
+    instructions_sysvar: &AccountInfo,
+    signing_key: &Pubkey,
+    message: &[u8],
+) -> Result<[u8; 64]> {
+    let ix = get_instruction_relative(-1, instructions_sysvar)
+        .map_err(|_| error!(CypherMedError::AttestationInstructionMissing))?;
+    require_keys_eq!(
+        ix.program_id,
+        ed25519_program::ID,
+        CypherMedError::AttestationInstructionMissing
+    );
+
+    let data = &ix.data;
+    require!(
+        data.len() >= ED25519_HEADER_LEN + ED25519_OFFSETS_LEN && data[0] == 1,
+        CypherMedError::InvalidAttestation
+    );
+
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let offsets = ED25519_HEADER_LEN;
+    let signature_offset = read_u16(offsets) as usize;
+    let signature_ix = read_u16(offsets + 2);
+    let public_key_offset = read_u16(offsets + 4) as usize;
+    let public_key_ix = read_u16(offsets + 6);
+    let message_offset = read_u16(offsets + 8) as usize;
+    let message_size = read_u16(offsets + 10) as usize;
+    let message_ix = read_u16(offsets + 12);
+    require!(
+        signature_ix == CURRENT_INSTRUCTION
+            && public_key_ix == CURRENT_INSTRUCTION
+            && message_ix == CURRENT_INSTRUCTION,
+        CypherMedError::InvalidAttestation
+    );
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(CypherMedError::InvalidAttestation)?;
+    let signed_message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(CypherMedError::InvalidAttestation)?;
+    require!(
+        public_key == signing_key.as_ref() && signed_message == message,
+        CypherMedError::InvalidAttestation
+    );
+
+    let signature = data
+        .get(signature_offset..signature_offset + 64)
+        .ok_or(CypherMedError::InvalidAttestation)?;
+    Ok(signature.try_into().unwrap())
+}
+
+#[event]
+pub struct RecordAttestedEvent {
+    pub record: Pubkey,
+    pub provider: Pubkey,
+    pub signing_key: Pubkey,
+    pub version: u32,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::instruction::{BorrowedInstruction, Instruction};
+    use solana_instructions_sysvar::{construct_instructions_data, store_current_index_checked};
+
+    const SIGNATURE: [u8; 64] = [7; 64];
+
+    fn signer() -> Pubkey {
+        Pubkey::new_from_array([1; 32])
+    }
+
+    fn message() -> Vec<u8> {
+        let hash = ContentHash {
+            algorithm: crate::state::HashAlgorithm::Sha256,
+            digest: [2; 32],
+        };
+        attestation_message(&Pubkey::new_from_array([3; 32]), &hash, 4)
+    }
+
+    /// Ed25519 program instruction data carrying one signature per entry,
+    /// with offsets pointing at instruction `data_ix`
+    fn ed25519_data(entries: &[(Pubkey, &[u8])], data_ix: u16) -> Vec<u8> {
+        let mut offsets = vec![entries.len() as u8, 0];
+        let mut payload = Vec::new();
+        let start = ED25519_HEADER_LEN + entries.len() * ED25519_OFFSETS_LEN;
+        for (public_key, message) in entries {
+            let public_key_offset = start + payload.len();
+            payload.extend_from_slice(public_key.as_ref());
+            let signature_offset = start + payload.len();
+            payload.extend_from_slice(&SIGNATURE);
+            let message_offset = start + payload.len();
+            payload.extend_from_slice(message);
+
+            for field in [
+                signature_offset as u16,
+                data_ix,
+                public_key_offset as u16,
+                data_ix,
+                message_offset as u16,
+                message.len() as u16,
+                data_ix,
+            ] {
+                offsets.extend_from_slice(&field.to_le_bytes());
+            }
+        }
+        offsets.extend_from_slice(&payload);
+        offsets
+    }
+
+    /// Instructions sysvar data for `previous` followed by the current instruction
+    fn sysvar_data(previous: &Instruction) -> Vec<u8> {
+        let current = Instruction::new_with_bytes(crate::ID, &[], Vec::new());
+        let borrowed: Vec<BorrowedInstruction> = [previous, &current]
+            .iter()
+            .map(|ix| BorrowedInstruction {
+                program_id: &ix.program_id,
+                accounts: Vec::new(),
+                data: &ix.data,
+            })
+            .collect();
+        let mut data = construct_instructions_data(&borrowed);
+        store_current_index_checked(&mut data, 1).unwrap();
+        data
+    }
+
+    fn verify(previous: Instruction, signing_key: &Pubkey, message: &[u8]) -> Result<[u8; 64]> {
+        let key = solana_sdk_ids::sysvar::instructions::ID;
+        let owner = solana_sdk_ids::sysvar::ID;
+        let mut lamports = 0;
+        let mut data = sysvar_data(&previous);
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        verify_ed25519_attestation(&info, signing_key, message)
+    }
+
+    fn ed25519_ix(data: Vec<u8>) -> Instruction {
+        Instruction::new_with_bytes(ed25519_program::ID, &data, Vec::new())
+    }
+
+    #[test]
+    fn matching_signature_is_accepted() {
+        let data = ed25519_data(&[(signer(), &message())], CURRENT_INSTRUCTION);
+        assert_eq!(verify(ed25519_ix(data), &signer(), &message()).unwrap(), SIGNATURE);
+    }
+
+    #[test]
+    fn wrong_signer_is_rejected() {
+        let other = Pubkey::new_from_array([9; 32]);
+        let data = ed25519_data(&[(other, &message())], CURRENT_INSTRUCTION);
+        assert_eq!(
+            verify(ed25519_ix(data), &signer(), &message()).unwrap_err(),
+            error!(CypherMedError::InvalidAttestation)
+        );
+    }
+
+    #[test]
+    fn wrong_message_is_rejected() {
+        let mut other = message();
+        *other.last_mut().unwrap() ^= 1;
+        let data = ed25519_data(&[(signer(), &other)], CURRENT_INSTRUCTION);
+        assert_eq!(
+            verify(ed25519_ix(data), &signer(), &message()).unwrap_err(),
+            error!(CypherMedError::InvalidAttestation)
+        );
+    }
+
+    #[test]
+    fn multiple_signatures_are_rejected() {
+        let message = message();
+        let data = ed25519_data(
+            &[(signer(), &message), (signer(), &message)],
+            CURRENT_INSTRUCTION,
+        );
+        assert_eq!(
+            verify(ed25519_ix(data), &signer(), &message).unwrap_err(),
+            error!(CypherMedError::InvalidAttestation)
+        );
+    }
+
+    #[test]
+    fn offsets_into_other_instructions_are_rejected() {
+        // Offsets naming instruction 0 explicitly rather than "this instruction"
+        let data = ed25519_data(&[(signer(), &message())], 0);
+        assert_eq!(
+            verify(ed25519_ix(data), &signer(), &message()).unwrap_err(),
+            error!(CypherMedError::InvalidAttestation)
+        );
+    }
+
+    #[test]
+    fn out_of_bounds_offsets_are_rejected() {
+        let mut data = ed25519_data(&[(signer(), &message())], CURRENT_INSTRUCTION);
+        let len = data.len() as u16;
+        data[ED25519_HEADER_LEN + 8..ED25519_HEADER_LEN + 10].copy_from_slice(&len.to_le_bytes());
+        assert_eq!(
+            verify(ed25519_ix(data), &signer(), &message()).unwrap_err(),
+            error!(CypherMedError::InvalidAttestation)
+        );
+    }
+
+    #[test]
+    fn previous_instruction_must_be_ed25519() {
+        let data = ed25519_data(&[(signer(), &message())], CURRENT_INSTRUCTION);
+        let previous = Instruction::new_with_bytes(crate::ID, &data, Vec::new());
+        assert_eq!(
+            verify(previous, &signer(), &message()).unwrap_err(),
+            error!(CypherMedError::AttestationInstructionMissing)
+        );
+    }
+}
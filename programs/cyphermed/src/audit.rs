@@ -0,0 +1,148 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use crate::state::*;
+use crate::errors::CypherMedError;
+
+/// SPL account compression program (owns compressed audit trees)
+pub const ACCOUNT_COMPRESSION_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+
+/// SPL noop program (carries full compressed audit entries in instruction data)
+pub const NOOP_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+/// Anchor discriminator of account compression's `init_empty_merkle_tree`
+const INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR: [u8; 8] = [191, 11, 119, 7, 180, 107, 220, 110];
+
+/// Anchor discriminator of account compression's `append`
+const APPEND_DISCRIMINATOR: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+
+/// Accounts needed when the patient uses the compressed audit mode
+#[derive(Accounts)]
+pub struct CompressedAudit<'info> {
+    /// CHECK: Must match `Patient::audit_tree` (verified in `write_audit_entry`)
+    #[account(mut)]
+    pub audit_tree: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Account compression program
+    #[account(address = ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Noop program
+    #[account(address = NOOP_ID)]
+    pub noop_program: Option<UncheckedAccount<'info>>,
+}
+
+/// Write a populated audit entry using the patient's configured audit mode
+///
+/// The entry's `sequence` and `prev_hash` are assigned here when it is
+/// linked into the patient's hash chain. In compressed mode the chain hash
+/// becomes the Merkle leaf and the full entry is logged through noop.
+pub fn write_audit_entry<'info>(
+    patient: &mut Account<'info, Patient>,
+    audit_log: Option<&mut Account<'info, AuditLog>>,
+    compressed: &CompressedAudit<'info>,
+    mut entry: AuditLog,
+) -> Result<()> {
+    patient.append_audit(&mut entry)?;
+
+    match patient.audit_mode {
+        AuditMode::Account => {
+            let audit_log = audit_log.ok_or(CypherMedError::AuditAccountRequired)?;
+            audit_log.set_inner(entry);
+        }
+        AuditMode::Compressed => {
+            require!(audit_log.is_none(), CypherMedError::AuditAccountNotAllowed);
+            append_compressed_entry(patient, compressed, &entry)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn append_compressed_entry<'info>(
+    patient: &Account<'info, Patient>,
+    compressed: &CompressedAudit<'info>,
+    entry: &AuditLog,
+) -> Result<()> {
+    let (Some(tree), Some(compression_program), Some(noop_program)) = (
+        compressed.audit_tree.as_ref(),
+        compressed.compression_program.as_ref(),
+        compressed.noop_program.as_ref(),
+    ) else {
+        return err!(CypherMedError::CompressedAuditAccountsRequired);
+    };
+    require!(
+        patient.audit_tree == Some(tree.key()),
+        CypherMedError::InvalidAuditTree
+    );
+
+    // Log the full entry so indexers can rebuild the leaf
+    let mut payload = Vec::with_capacity(AuditLog::LEN);
+    entry.try_serialize(&mut payload)?;
+    invoke(
+        &Instruction {
+            program_id: NOOP_ID,
+            accounts: vec![],
+            data: payload,
+        },
+        &[noop_program.to_account_info()],
+    )?;
+
+    let mut data = APPEND_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&entry.hash());
+    invoke_signed(
+        &Instruction {
+            program_id: ACCOUNT_COMPRESSION_ID,
+            accounts: vec![
+                AccountMeta::new(tree.key(), false),
+                AccountMeta::new_readonly(patient.key(), true),
+                AccountMeta::new_readonly(NOOP_ID, false),
+            ],
+            data,
+        },
+        &[
+            tree.to_account_info(),
+            patient.to_account_info(),
+            noop_program.to_account_info(),
+            compression_program.to_account_info(),
+        ],
+        &[&[b"patient", patient.authority.as_ref(), &[patient.bump]]],
+    )?;
+
+    Ok(())
+}
+
+/// Initialize an empty concurrent Merkle tree owned by the patient PDA
+pub fn init_audit_tree<'info>(
+    patient: &Account<'info, Patient>,
+    tree: &AccountInfo<'info>,
+    compression_program: &AccountInfo<'info>,
+    noop_program: &AccountInfo<'info>,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Result<()> {
+    let mut data = INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&max_depth.to_le_bytes());
+    data.extend_from_slice(&max_buffer_size.to_le_bytes());
+
+    invoke_signed(
+        &Instruction {
+            program_id: ACCOUNT_COMPRESSION_ID,
+            accounts: vec![
+                AccountMeta::new(tree.key(), false),
+                AccountMeta::new_readonly(patient.key(), true),
+                AccountMeta::new_readonly(NOOP_ID, false),
+            ],
+            data,
+        },
+        &[
+            tree.clone(),
+            patient.to_account_info(),
+            noop_program.clone(),
+            compression_program.clone(),
+        ],
+        &[&[b"patient", patient.authority.as_ref(), &[patient.bump]]],
+    )?;
+
+    Ok(())
+}
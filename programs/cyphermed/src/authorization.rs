@@ -0,0 +1,422 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::utils::is_grant_expired;
+
+/// Operation a subject wants to perform on a record
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecordOperation {
+    View,
+    Create,
+    Modify,
+    Delete,
+}
+
+/// Record an operation applies to: an existing account, or the type of a
+/// record that is about to be created
+#[derive(Clone, Copy)]
+pub enum RecordTarget<'a> {
+    Existing(&'a MedicalRecord),
+    New(RecordTypeCode),
+}
+
+impl RecordTarget<'_> {
+    pub fn record_type(&self) -> RecordTypeCode {
+        match self {
+            RecordTarget::Existing(record) => record.record_type,
+            RecordTarget::New(record_type) => *record_type,
+        }
+    }
+}
+
+/// Outcome of an authorization check
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Authorization {
+    pub decision: AccessDecision,
+    /// Role the subject acts under; `Patient` when no grant applies
+    pub role: Role,
+}
+
+impl Authorization {
+    fn new(decision: AccessDecision, role: Role) -> Self {
+        Self { decision, role }
+    }
+}
+
+/// Decide whether `subject` may perform `operation` on `target` at `now`.
+///
+/// The patient may do anything to their own records except modify one a
+/// provider authored, which goes through an amendment request instead. A
+/// record's creator may modify or delete it under the role they created it
+/// with. Everyone else needs an active, unexpired grant carrying the
+/// operation's permission flag and covering the record type.
+pub fn authorize(
+    patient: &Patient,
+    subject: &Pubkey,
+    grant: Option<&AccessGrant>,
+    operation: RecordOperation,
+    target: RecordTarget,
+    now: i64,
+) -> Authorization {
+    let fallback_role = grant.map_or(Role::Patient, |grant| grant.role);
+
+    if !patient.is_active {
+        return Authorization::new(AccessDecision::PatientInactive, fallback_role);
+    }
+    if let RecordTarget::Existing(record) = target {
+        if !record.is_active {
+            return Authorization::new(AccessDecision::RecordInactive, fallback_role);
+        }
+    }
+    if *subject == patient.authority {
+        if let RecordTarget::Existing(record) = target {
+            if operation == RecordOperation::Modify && record.created_by != patient.authority {
+                return Authorization::new(AccessDecision::AmendmentRequired, Role::Patient);
+            }
+        }
+        return Authorization::new(AccessDecision::Allowed, Role::Patient);
+    }
+    if let RecordTarget::Existing(record) = target {
+        let creator_may_act = matches!(operation, RecordOperation::Modify | RecordOperation::Delete);
+        if creator_may_act && *subject == record.created_by {
+            return Authorization::new(AccessDecision::Allowed, record.created_by_role);
+        }
+    }
+
+    let Some(grant) = grant else {
+        return Authorization::new(AccessDecision::NoAccessGrant, fallback_role);
+    };
+
+    let permitted = match operation {
+        RecordOperation::View => grant.can_view,
+        RecordOperation::Create => grant.can_create,
+        RecordOperation::Modify => grant.can_modify,
+        RecordOperation::Delete => grant.can_delete,
+    };
+    let decision = if !grant.is_active {
+        AccessDecision::GrantInactive
+    } else if is_grant_expired(grant.expires_at, now) {
+        AccessDecision::GrantExpired
+    } else if !permitted {
+        match operation {
+            RecordOperation::View => AccessDecision::NoViewPermission,
+            RecordOperation::Create => AccessDecision::NoCreatePermission,
+            RecordOperation::Modify => AccessDecision::NoModifyPermission,
+            RecordOperation::Delete => AccessDecision::NoDeletePermission,
+        }
+    } else if !grant.allowed_record_types.contains(&target.record_type()) {
+        AccessDecision::RecordTypeNotAllowed
+    } else {
+        AccessDecision::Allowed
+    };
+    Authorization::new(decision, grant.role)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    const OPERATIONS: [RecordOperation; 4] = [
+        RecordOperation::View,
+        RecordOperation::Create,
+        RecordOperation::Modify,
+        RecordOperation::Delete,
+    ];
+
+    fn owner() -> Pubkey {
+        Pubkey::new_from_array([1; 32])
+    }
+
+    fn creator() -> Pubkey {
+        Pubkey::new_from_array([2; 32])
+    }
+
+    fn provider() -> Pubkey {
+        Pubkey::new_from_array([3; 32])
+    }
+
+    fn patient(is_active: bool) -> Patient {
+        Patient {
+            authority: owner(),
+            name: "Test Patient".to_string(),
+            date_of_birth: 0,
+            created_at: 0,
+            updated_at: 0,
+            record_count: 0,
+            access_grant_count: 0,
+            is_active,
+            emergency_contact: None,
+            audit_count: 0,
+            audit_head_hash: [0; 32],
+            audit_mode: AuditMode::Account,
+            audit_tree: None,
+            deletion_cosign_window: None,
+            data_key_epoch: 0,
+            bump: 255,
+        }
+    }
+
+    fn record(is_active: bool) -> MedicalRecord {
+        MedicalRecord {
+            patient: Pubkey::new_from_array([9; 32]),
+            created_by: creator(),
+            created_by_role: Role::Hospital,
+            record_type: RecordType::LabResult.code(),
+            record_id: "rec-1".to_string(),
+            data_hash: ContentHash {
+                algorithm: HashAlgorithm::Sha256,
+                digest: [7; 32],
+            },
+            storage: None,
+            created_at: 0,
+            modified_at: 0,
+            content_updated_at: 0,
+            last_accessed: 0,
+            access_count: 0,
+            is_active,
+            deleted_by: None,
+            deleted_at: None,
+            retention_flagged_at: None,
+            metadata: None,
+            version: 1,
+            amendment_count: 0,
+            attested: false,
+            attested_by: None,
+            key_epoch: 0,
+            needs_rekey: false,
+            attachment_count: 0,
+            next_attachment_index: 0,
+            clinical_codes: Vec::new(),
+            bump: 255,
+        }
+    }
+
+    struct GrantFlags {
+        is_active: bool,
+        expires_at: Option<i64>,
+        can_view: bool,
+        can_create: bool,
+        can_modify: bool,
+        can_delete: bool,
+        covers_type: bool,
+    }
+
+    fn grant(flags: &GrantFlags) -> AccessGrant {
+        AccessGrant {
+            patient: Pubkey::new_from_array([9; 32]),
+            provider: provider(),
+            role: Role::Doctor,
+            allowed_record_types: if flags.covers_type {
+                vec![RecordType::Prescription.code(), RecordType::LabResult.code()]
+            } else {
+                vec![RecordType::Prescription.code()]
+            },
+            granted_at: 0,
+            expires_at: flags.expires_at,
+            is_active: flags.is_active,
+            can_create: flags.can_create,
+            can_modify: flags.can_modify,
+            can_view: flags.can_view,
+            can_delete: flags.can_delete,
+            reason: None,
+            encryption_key_id: None,
+            revoked_by: None,
+            revoked_at: None,
+            bump: 255,
+        }
+    }
+
+    /// Every combination of grant flags, expiry and record-type coverage
+    fn grant_matrix() -> Vec<GrantFlags> {
+        let mut matrix = Vec::new();
+        for bits in 0..64u8 {
+            for expires_at in [None, Some(NOW - 1), Some(NOW), Some(NOW + 1)] {
+                matrix.push(GrantFlags {
+                    is_active: bits & 1 != 0,
+                    expires_at,
+                    can_view: bits & 2 != 0,
+                    can_create: bits & 4 != 0,
+                    can_modify: bits & 8 != 0,
+                    can_delete: bits & 16 != 0,
+                    covers_type: bits & 32 != 0,
+                });
+            }
+        }
+        matrix
+    }
+
+    fn expected(flags: &GrantFlags, operation: RecordOperation) -> AccessDecision {
+        if !flags.is_active {
+            return AccessDecision::GrantInactive;
+        }
+        if flags.expires_at.is_some_and(|expires_at| expires_at < NOW) {
+            return AccessDecision::GrantExpired;
+        }
+        let (permitted, missing) = match operation {
+            RecordOperation::View => (flags.can_view, AccessDecision::NoViewPermission),
+            RecordOperation::Create => (flags.can_create, AccessDecision::NoCreatePermission),
+            RecordOperation::Modify => (flags.can_modify, AccessDecision::NoModifyPermission),
+            RecordOperation::Delete => (flags.can_delete, AccessDecision::NoDeletePermission),
+        };
+        if !permitted {
+            return missing;
+        }
+        if !flags.covers_type {
+            return AccessDecision::RecordTypeNotAllowed;
+        }
+        AccessDecision::Allowed
+    }
+
+    #[test]
+    fn grant_matrix_on_existing_record() {
+        let patient = patient(true);
+        let record = record(true);
+        for flags in grant_matrix() {
+            let grant = grant(&flags);
+            for operation in OPERATIONS {
+                let auth = authorize(
+                    &patient,
+                    &provider(),
+                    Some(&grant),
+                    operation,
+                    RecordTarget::Existing(&record),
+                    NOW,
+                );
+                assert_eq!(auth.decision, expected(&flags, operation), "{operation:?}");
+                assert_eq!(auth.role, Role::Doctor);
+            }
+        }
+    }
+
+    #[test]
+    fn grant_matrix_on_new_record() {
+        let patient = patient(true);
+        for flags in grant_matrix() {
+            let grant = grant(&flags);
+            let auth = authorize(
+                &patient,
+                &provider(),
+                Some(&grant),
+                RecordOperation::Create,
+                RecordTarget::New(RecordType::LabResult.code()),
+                NOW,
+            );
+            assert_eq!(auth.decision, expected(&flags, RecordOperation::Create));
+        }
+    }
+
+    #[test]
+    fn missing_grant_is_denied() {
+        let patient = patient(true);
+        let record = record(true);
+        for operation in OPERATIONS {
+            let auth = authorize(
+                &patient,
+                &provider(),
+                None,
+                operation,
+                RecordTarget::Existing(&record),
+                NOW,
+            );
+            assert_eq!(auth.decision, AccessDecision::NoAccessGrant);
+        }
+    }
+
+    #[test]
+    fn owner_is_allowed_on_own_active_records() {
+        let patient = patient(true);
+        let mut record = record(true);
+        record.created_by = owner();
+        for operation in OPERATIONS {
+            let auth = authorize(
+                &patient,
+                &owner(),
+                None,
+                operation,
+                RecordTarget::Existing(&record),
+                NOW,
+            );
+            assert_eq!(auth, Authorization::new(AccessDecision::Allowed, Role::Patient));
+        }
+    }
+
+    #[test]
+    fn owner_must_amend_provider_authored_records() {
+        let patient = patient(true);
+        let record = record(true);
+        for operation in OPERATIONS {
+            let auth = authorize(
+                &patient,
+                &owner(),
+                None,
+                operation,
+                RecordTarget::Existing(&record),
+                NOW,
+            );
+            let expected = match operation {
+                RecordOperation::Modify => AccessDecision::AmendmentRequired,
+                _ => AccessDecision::Allowed,
+            };
+            assert_eq!(auth, Authorization::new(expected, Role::Patient));
+        }
+    }
+
+    #[test]
+    fn creator_may_modify_and_delete_under_creation_role() {
+        let patient = patient(true);
+        let record = record(true);
+        for operation in OPERATIONS {
+            let auth = authorize(
+                &patient,
+                &creator(),
+                None,
+                operation,
+                RecordTarget::Existing(&record),
+                NOW,
+            );
+            match operation {
+                RecordOperation::Modify | RecordOperation::Delete => {
+                    assert_eq!(auth, Authorization::new(AccessDecision::Allowed, Role::Hospital));
+                }
+                _ => assert_eq!(auth.decision, AccessDecision::NoAccessGrant),
+            }
+        }
+    }
+
+    #[test]
+    fn inactive_patient_or_record_denies_everyone() {
+        let active_grant = grant(&GrantFlags {
+            is_active: true,
+            expires_at: None,
+            can_view: true,
+            can_create: true,
+            can_modify: true,
+            can_delete: true,
+            covers_type: true,
+        });
+        for subject in [owner(), creator(), provider()] {
+            for operation in OPERATIONS {
+                let auth = authorize(
+                    &patient(false),
+                    &subject,
+                    Some(&active_grant),
+                    operation,
+                    RecordTarget::Existing(&record(true)),
+                    NOW,
+                );
+                assert_eq!(auth.decision, AccessDecision::PatientInactive);
+
+                let auth = authorize(
+                    &patient(true),
+                    &subject,
+                    Some(&active_grant),
+                    operation,
+                    RecordTarget::Existing(&record(false)),
+                    NOW,
+                );
+                assert_eq!(auth.decision, AccessDecision::RecordInactive);
+            }
+        }
+    }
+}
@@ -110,4 +110,271 @@ pub enum CypherMedError {
     
     #[msg("Patient account is already active")]
     PatientAlreadyActive,
+    
+    #[msg("Invalid rate limit configuration")]
+    InvalidRateLimitConfig,
+    
+    #[msg("Emergency access rate limit exceeded for this responder")]
+    EmergencyRateLimitExceeded,
+    
+    #[msg("Emergency access requires a second responder's co-signature")]
+    EmergencyCoSignatureRequired,
+    
+    #[msg("Co-signer must be a different responder")]
+    InvalidCoSigner,
+    
+    #[msg("Storage CID is too long (max 100 characters)")]
+    StorageCidTooLong,
+    
+    #[msg("An audit log account is required in account audit mode")]
+    AuditAccountRequired,
+    
+    #[msg("Audit log accounts must not be passed in compressed audit mode")]
+    AuditAccountNotAllowed,
+    
+    #[msg("Audit tree, compression and noop accounts are required in compressed audit mode")]
+    CompressedAuditAccountsRequired,
+    
+    #[msg("Audit tree does not belong to this patient")]
+    InvalidAuditTree,
+    
+    #[msg("Patient has no compressed audit tree")]
+    AuditTreeNotInitialized,
+    
+    #[msg("Patient already has a compressed audit tree")]
+    AuditTreeAlreadyInitialized,
+    
+    #[msg("Access grant does not allow viewing records")]
+    NoViewPermission,
+    
+    #[msg("Access grant does not allow creating records")]
+    NoCreatePermission,
+    
+    #[msg("Access grant does not allow modifying records")]
+    NoModifyPermission,
+    
+    #[msg("Access grant does not cover this record type")]
+    RecordTypeNotAllowed,
+    
+    #[msg("Access grant does not allow deleting records")]
+    NoDeletePermission,
+    
+    #[msg("Deletion co-sign window must be positive")]
+    InvalidDeletionCoSignWindow,
+    
+    #[msg("A pending deletion account is required when the patient must co-sign")]
+    PendingDeletionRequired,
+    
+    #[msg("Pending deletion account must not be passed for an immediate deletion")]
+    PendingDeletionNotAllowed,
+    
+    #[msg("Pending deletion is still awaiting the patient's co-signature")]
+    DeletionCoSignPending,
+    
+    #[msg("Record is already active")]
+    RecordAlreadyActive,
+    
+    #[msg("Restoration reason is required")]
+    RestoreReasonRequired,
+    
+    #[msg("Restoration reason is too long (max 300 characters)")]
+    RestoreReasonTooLong,
+    
+    #[msg("Restore grace period has expired; only the patient can restore this record")]
+    RestoreGracePeriodExpired,
+    
+    #[msg("Every prior version, amendment request and attachment of the record must be passed for a purge")]
+    RecordVersionsMissing,
+    
+    #[msg("Account is not a version of this record")]
+    InvalidRecordVersion,
+    
+    #[msg("Invalid retention policy: periods must be positive and minimum must not exceed maximum")]
+    InvalidRetentionPolicy,
+    
+    #[msg("No legal authority is configured")]
+    LegalAuthorityNotConfigured,
+    
+    #[msg("Legal hold reason is required")]
+    LegalHoldReasonRequired,
+    
+    #[msg("Legal hold reason is too long (max 200 characters)")]
+    LegalHoldReasonTooLong,
+    
+    #[msg("Record is under a legal hold")]
+    RecordUnderLegalHold,
+    
+    #[msg("Record has not reached its minimum retention period")]
+    MinimumRetentionNotMet,
+    
+    #[msg("Record has not passed its maximum retention period")]
+    RetentionNotExpired,
+    
+    #[msg("Record has already been flagged for expired retention")]
+    RetentionAlreadyFlagged,
+    
+    #[msg("Patients must request an amendment to change a provider-authored record")]
+    AmendmentRequired,
+    
+    #[msg("Record was authored by the patient and can be updated directly")]
+    AmendmentNotApplicable,
+    
+    #[msg("Amendment statement is required")]
+    AmendmentStatementRequired,
+    
+    #[msg("Amendment statement is too long (max 500 characters)")]
+    AmendmentStatementTooLong,
+    
+    #[msg("Amendment request has already been resolved")]
+    AmendmentNotPending,
+    
+    #[msg("A statement of disagreement can only follow a rejected amendment")]
+    AmendmentNotRejected,
+    
+    #[msg("A statement of disagreement has already been filed")]
+    DisagreementAlreadyFiled,
+    
+    #[msg("Instructions sysvar is required to verify an attestation")]
+    InstructionsSysvarRequired,
+    
+    #[msg("Attestation requires an Ed25519 verify instruction immediately before this one")]
+    AttestationInstructionMissing,
+    
+    #[msg("Ed25519 signature does not match the expected signer or message")]
+    InvalidAttestation,
+    
+    #[msg("Attachment storage CID is required")]
+    AttachmentCidRequired,
+    
+    #[msg("Attachment size must be greater than zero")]
+    InvalidAttachmentSize,
+    
+    #[msg("Remove the record's attachments before purging it")]
+    AttachmentsRemaining,
+    
+    #[msg("Invalid IPFS CID")]
+    InvalidIpfsCid,
+    
+    #[msg("Invalid Arweave transaction id")]
+    InvalidArweaveTxId,
+    
+    #[msg("Storage URL must be an https URL of at most 200 characters")]
+    InvalidStorageUrl,
+    
+    #[msg("Custom storage scheme is not registered in the program config")]
+    StorageSchemeNotRegistered,
+    
+    #[msg("Invalid custom storage reference (1-100 printable characters)")]
+    InvalidStorageReference,
+    
+    #[msg("Invalid storage scheme name (1-16 lowercase letters, digits or '-')")]
+    InvalidStorageScheme,
+    
+    #[msg("Too many custom storage schemes registered")]
+    StorageSchemeLimitReached,
+    
+    #[msg("Grantee has not registered an encryption key")]
+    EncryptionKeyNotRegistered,
+    
+    #[msg("Key envelope account is required to share the data key")]
+    KeyEnvelopeRequired,
+    
+    #[msg("Key envelope account was supplied without a wrapped key")]
+    WrappedKeyRequired,
+    
+    #[msg("Encryption key not found")]
+    EncryptionKeyNotFound,
+    
+    #[msg("Encryption key has been rotated or revoked")]
+    EncryptionKeyNotActive,
+    
+    #[msg("Encryption key has already been revoked")]
+    EncryptionKeyAlreadyRevoked,
+    
+    #[msg("A key rotation is already in progress")]
+    KeyRotationInProgress,
+    
+    #[msg("No key rotation is in progress")]
+    NoKeyRotationInProgress,
+    
+    #[msg("Too many records awaiting re-key")]
+    TooManyPendingRekeys,
+    
+    #[msg("Record is already encrypted under the current data key")]
+    RecordAlreadyRekeyed,
+    
+    #[msg("Key rotation status account is required to re-key this record")]
+    KeyRotationRequired,
+    
+    #[msg("Escrow threshold must be between 2 and the number of custodians")]
+    InvalidEscrowThreshold,
+    
+    #[msg("Too many escrow custodians")]
+    TooManyCustodians,
+    
+    #[msg("Escrow custodians and share indexes must be unique and non-zero")]
+    InvalidEscrowShare,
+    
+    #[msg("Signer is not a custodian of this key escrow")]
+    NotEscrowCustodian,
+    
+    #[msg("No emergency release window is open for this key escrow")]
+    EscrowReleaseNotOpen,
+    
+    #[msg("Custodian has already released their share in this window")]
+    EscrowShareAlreadyReleased,
+    
+    #[msg("Too many clinical codes on one record")]
+    TooManyClinicalCodes,
+    
+    #[msg("Clinical code is not valid for its code system")]
+    InvalidClinicalCode,
+    
+    #[msg("Clinical code is listed more than once")]
+    DuplicateClinicalCode,
+    
+    #[msg("Record type has been retired")]
+    RecordTypeRetired,
+    
+    #[msg("Record type name must be 1-32 printable characters")]
+    InvalidRecordTypeName,
+    
+    #[msg("Too many record types with retention policies")]
+    RetentionPolicyLimitReached,
+    
+    #[msg("A release window is already open for another responder")]
+    EscrowReleaseInProgress,
+    
+    #[msg("Escrowed key predates the patient's current data key")]
+    EscrowKeyOutdated,
+    
+    #[msg("Records marked for re-key are still pending")]
+    RekeysPending,
+    
+    #[msg("Account is not the expected amendment request for this record")]
+    InvalidAmendmentRequest,
+    
+    #[msg("Account is not an attachment of this record")]
+    InvalidAttachment,
+    
+    #[msg("Requester of the outstanding deletion must be passed to be refunded")]
+    DeletionRequesterRequired,
+    
+    #[msg("A record with this id was purged; the id cannot be reused")]
+    RecordIdPurged,
+    
+    #[msg("Emergency responder is not registered")]
+    ResponderNotRegistered,
+    
+    #[msg("Patient's audit sequence is exhausted")]
+    AuditSequenceOverflow,
+    
+    #[msg("Encryption key list is full and has no revoked key without envelopes to drop")]
+    EncryptionKeyLimitReached,
+    
+    #[msg("Account is not a key envelope wrapped to this key")]
+    InvalidKeyEnvelope,
+    
+    #[msg("Record is already awaiting re-key")]
+    RecordAlreadyPendingRekey,
 }
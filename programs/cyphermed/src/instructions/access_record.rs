@@ -1,52 +1,30 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
-use crate::errors::CypherMedError;
-use crate::utils::*;
+use crate::audit::*;
+use crate::authorization::*;
 
 /// Access (view) a medical record and create audit log
+///
+/// Denied attempts are committed rather than reverted so that the audit
+/// entry survives; the outcome is returned to the caller as return data.
 pub fn access_record(
     ctx: Context<AccessRecord>,
     client_info: Option<String>,
-) -> Result<()> {
-    let patient = &ctx.accounts.patient;
+) -> Result<AccessOutcome> {
+    let patient = &mut ctx.accounts.patient;
     let record = &mut ctx.accounts.record;
     let clock = Clock::get()?;
 
-    // Verify patient and record are active
-    require!(patient.is_active, CypherMedError::PatientInactive);
-    require!(record.is_active, CypherMedError::RecordInactive);
-
     // Check if accessor is authorized
-    let is_patient = ctx.accounts.accessor.key() == patient.authority;
-    let mut accessor_role = Role::Patient;
-    let mut success = true;
-    let mut failure_reason = None;
-
-    if !is_patient {
-        match &ctx.accounts.access_grant {
-            Some(grant) => {
-                if !grant.is_active {
-                    success = false;
-                    failure_reason = Some("Access grant is not active".to_string());
-                } else if is_grant_expired(grant.expires_at, clock.unix_timestamp) {
-                    success = false;
-                    failure_reason = Some("Access grant has expired".to_string());
-                } else if !grant.can_view {
-                    success = false;
-                    failure_reason = Some("No view permission".to_string());
-                } else if !grant.allowed_record_types.contains(&record.record_type) {
-                    success = false;
-                    failure_reason = Some("Record type not allowed".to_string());
-                } else {
-                    accessor_role = grant.role;
-                }
-            }
-            None => {
-                success = false;
-                failure_reason = Some("No access grant found".to_string());
-            }
-        }
-    }
+    let Authorization { decision, role: accessor_role } = authorize(
+        patient,
+        &ctx.accounts.accessor.key(),
+        ctx.accounts.access_grant.as_deref(),
+        RecordOperation::View,
+        RecordTarget::Existing(record),
+        clock.unix_timestamp,
+    );
+    let success = decision.is_allowed();
 
     // If access was successful, update record statistics
     if success {
@@ -55,40 +33,63 @@ pub fn access_record(
     }
 
     // Create audit log entry (always log, even failures)
-    let audit = &mut ctx.accounts.audit_log;
-    audit.patient = patient.key();
-    audit.record = record.key();
-    audit.accessor = ctx.accounts.accessor.key();
-    audit.accessor_role = accessor_role;
-    audit.action = AccessAction::View;
-    audit.record_type = record.record_type;
-    audit.timestamp = clock.unix_timestamp;
-    audit.success = success;
-    audit.failure_reason = failure_reason.clone();
-    audit.is_emergency = false;
-    audit.emergency_justification = None;
-    audit.client_info = client_info;
-    audit.metadata = Some(format!("Record access attempt by {:?}", accessor_role));
-    audit.bump = ctx.bumps.audit_log;
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.accessor.key(),
+        accessor_role,
+        action: AccessAction::View,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success,
+        decision,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info,
+        metadata: Some(format!("Record access attempt by {:?}", accessor_role)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
 
     if !success {
-        msg!("Access denied: {}", failure_reason.unwrap_or_default());
-        return err!(CypherMedError::AccessDenied);
-    }
+        msg!("Access denied: {:?}", decision);
 
-    msg!(
-        "Record {} accessed by {} (role: {:?})",
-        record.key(),
-        ctx.accounts.accessor.key(),
-        accessor_role
-    );
+        emit!(AccessDeniedEvent {
+            patient: patient.key(),
+            record: record.key(),
+            accessor: ctx.accounts.accessor.key(),
+            audit_sequence: patient.audit_count - 1,
+            decision,
+            timestamp: clock.unix_timestamp,
+        });
+    } else {
+        msg!(
+            "Record {} accessed by {} (role: {:?}, {} attachments)",
+            record.key(),
+            ctx.accounts.accessor.key(),
+            accessor_role,
+            record.attachment_count
+        );
+    }
     
-    Ok(())
+    Ok(AccessOutcome {
+        granted: success,
+        decision,
+        attachment_count: if success { record.attachment_count } else { 0 },
+    })
 }
 
 #[derive(Accounts)]
 pub struct AccessRecord<'info> {
     #[account(
+        mut,
         seeds = [b"patient", patient.authority.as_ref()],
         bump = patient.bump,
     )]
@@ -113,18 +114,37 @@ pub struct AccessRecord<'info> {
         payer = accessor,
         space = AuditLog::LEN,
         seeds = [
-            b"audit", 
-            record.key().as_ref(), 
-            accessor.key().as_ref(), 
-            b"access",
-            &record.access_count.to_le_bytes()
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
         ],
         bump
     )]
-    pub audit_log: Account<'info, AuditLog>,
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
 
     #[account(mut)]
     pub accessor: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
+
+/// Result of an `access_record` call, returned via return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AccessOutcome {
+    pub granted: bool,
+    pub decision: AccessDecision,
+    /// Attachments on the record (0 when access was denied)
+    pub attachment_count: u32,
+}
+
+#[event]
+pub struct AccessDeniedEvent {
+    pub patient: Pubkey,
+    pub record: Pubkey,
+    pub accessor: Pubkey,
+    pub audit_sequence: u64,
+    pub decision: AccessDecision,
+    pub timestamp: i64,
+}
@@ -0,0 +1,580 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::CypherMedError;
+use crate::audit::*;
+use crate::authorization::*;
+use crate::instructions::update_delete_record::{apply_content_update, ContentUpdate};
+use crate::instructions::key_rotation::complete_rekey;
+use crate::attestation::attest_record;
+
+/// Ask the authoring provider to correct a record (patient only)
+pub fn request_amendment(
+    ctx: Context<RequestAmendment>,
+    requested_change: String,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let record = &mut ctx.accounts.record;
+    let clock = Clock::get()?;
+
+    require!(patient.is_active, CypherMedError::PatientInactive);
+    require!(record.is_active, CypherMedError::RecordInactive);
+    require!(
+        record.created_by != patient.authority,
+        CypherMedError::AmendmentNotApplicable
+    );
+    validate_statement(&requested_change)?;
+
+    let amendment = &mut ctx.accounts.amendment;
+    amendment.record = record.key();
+    amendment.patient = patient.key();
+    amendment.index = record.amendment_count;
+    amendment.requested_change = requested_change;
+    amendment.status = AmendmentStatus::Pending;
+    amendment.provider_statement = None;
+    amendment.disagreement_statement = None;
+    amendment.resulting_version = None;
+    amendment.requested_at = clock.unix_timestamp;
+    amendment.responded_at = None;
+    amendment.bump = ctx.bumps.amendment;
+
+    record.amendment_count = record.amendment_count.checked_add(1).unwrap();
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::RequestAmendment,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Amendment #{} requested", amendment.index)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!("Amendment #{} requested on record {}", amendment.index, record.key());
+
+    emit!(AmendmentRequestedEvent {
+        record: record.key(),
+        patient: patient.key(),
+        amendment: amendment.key(),
+        index: amendment.index,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Accept an amendment, producing a new version of the record (authoring provider only)
+pub fn accept_amendment(
+    ctx: Context<AcceptAmendment>,
+    new_data_hash: Option<ContentHash>,
+    new_storage: Option<StorageLocator>,
+    new_metadata: Option<String>,
+    new_clinical_codes: Option<Vec<ClinicalCode>>,
+    statement: Option<String>,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let record = &mut ctx.accounts.record;
+    let amendment = &mut ctx.accounts.amendment;
+    let clock = Clock::get()?;
+
+    let auth = authorize(
+        patient,
+        &ctx.accounts.provider.key(),
+        None,
+        RecordOperation::Modify,
+        RecordTarget::Existing(record),
+        clock.unix_timestamp,
+    );
+    auth.decision.require_allowed()?;
+    require!(
+        amendment.status == AmendmentStatus::Pending,
+        CypherMedError::AmendmentNotPending
+    );
+    if let Some(ref statement) = statement {
+        validate_statement(statement)?;
+    }
+
+    let update_note = format!("Amendment #{} accepted", amendment.index);
+    let is_rekey = record.needs_rekey && new_data_hash.is_some();
+    apply_content_update(
+        record,
+        &mut ctx.accounts.record_version,
+        ctx.bumps.record_version,
+        ctx.accounts.config.as_deref(),
+        ctx.accounts.provider.key(),
+        &update_note,
+        ContentUpdate {
+            data_hash: new_data_hash,
+            key_epoch: patient.data_key_epoch,
+            storage: new_storage,
+            metadata: new_metadata,
+            clinical_codes: new_clinical_codes,
+        },
+        clock.unix_timestamp,
+    )?;
+    if is_rekey {
+        complete_rekey(record, ctx.accounts.key_rotation.as_mut())?;
+    }
+    attest_record(
+        record,
+        &ctx.accounts.provider_signing_key,
+        ctx.accounts.instructions_sysvar.as_ref(),
+        clock.unix_timestamp,
+    )?;
+
+    amendment.status = AmendmentStatus::Accepted;
+    amendment.provider_statement = statement;
+    amendment.resulting_version = Some(record.version);
+    amendment.responded_at = Some(clock.unix_timestamp);
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.provider.key(),
+        accessor_role: auth.role,
+        action: AccessAction::AcceptAmendment,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("{} as version {}", update_note, record.version)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!("{} on record {}", update_note, record.key());
+
+    emit!(AmendmentResolvedEvent {
+        record: record.key(),
+        amendment: amendment.key(),
+        provider: ctx.accounts.provider.key(),
+        accepted: true,
+        resulting_version: amendment.resulting_version,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Reject an amendment with a statement (authoring provider only)
+pub fn reject_amendment(
+    ctx: Context<RejectAmendment>,
+    statement: String,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let record = &ctx.accounts.record;
+    let amendment = &mut ctx.accounts.amendment;
+    let clock = Clock::get()?;
+
+    let auth = authorize(
+        patient,
+        &ctx.accounts.provider.key(),
+        None,
+        RecordOperation::Modify,
+        RecordTarget::Existing(record),
+        clock.unix_timestamp,
+    );
+    auth.decision.require_allowed()?;
+    require!(
+        amendment.status == AmendmentStatus::Pending,
+        CypherMedError::AmendmentNotPending
+    );
+    validate_statement(&statement)?;
+
+    amendment.status = AmendmentStatus::Rejected;
+    amendment.provider_statement = Some(statement);
+    amendment.responded_at = Some(clock.unix_timestamp);
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.provider.key(),
+        accessor_role: auth.role,
+        action: AccessAction::RejectAmendment,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Amendment #{} rejected", amendment.index)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!("Amendment #{} rejected on record {}", amendment.index, record.key());
+
+    emit!(AmendmentResolvedEvent {
+        record: record.key(),
+        amendment: amendment.key(),
+        provider: ctx.accounts.provider.key(),
+        accepted: false,
+        resulting_version: None,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Attach a statement of disagreement to a rejected amendment (patient only)
+pub fn file_disagreement(
+    ctx: Context<FileDisagreement>,
+    statement: String,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let amendment = &mut ctx.accounts.amendment;
+    let clock = Clock::get()?;
+
+    require!(patient.is_active, CypherMedError::PatientInactive);
+    require!(
+        amendment.status == AmendmentStatus::Rejected,
+        CypherMedError::AmendmentNotRejected
+    );
+    require!(
+        amendment.disagreement_statement.is_none(),
+        CypherMedError::DisagreementAlreadyFiled
+    );
+    validate_statement(&statement)?;
+
+    amendment.disagreement_statement = Some(statement);
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(amendment.record),
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::FileDisagreement,
+        record_type: Some(ctx.accounts.record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Disagreement filed on amendment #{}", amendment.index)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!("Statement of disagreement filed on amendment #{}", amendment.index);
+
+    emit!(DisagreementFiledEvent {
+        record: amendment.record,
+        amendment: amendment.key(),
+        patient: patient.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+fn validate_statement(statement: &str) -> Result<()> {
+    require!(!statement.is_empty(), CypherMedError::AmendmentStatementRequired);
+    require!(statement.len() <= 500, CypherMedError::AmendmentStatementTooLong);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RequestAmendment<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        mut,
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AmendmentRequest::LEN,
+        seeds = [
+            b"amendment",
+            record.key().as_ref(),
+            &record.amendment_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub amendment: Account<'info, AmendmentRequest>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAmendment<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", patient.authority.as_ref()],
+        bump = patient.bump,
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        mut,
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"amendment",
+            record.key().as_ref(),
+            &amendment.index.to_le_bytes()
+        ],
+        bump = amendment.bump,
+    )]
+    pub amendment: Account<'info, AmendmentRequest>,
+
+    /// Program config (required only for custom storage schemes)
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Option<Account<'info, ProgramConfig>>,
+
+    /// Patient's key rotation (required when re-keying a record marked for it)
+    #[account(
+        mut,
+        seeds = [b"key_rotation", patient.key().as_ref()],
+        bump = key_rotation.bump,
+    )]
+    pub key_rotation: Option<Account<'info, KeyRotation>>,
+
+    /// CHECK: Signing key PDA of the provider; if a key is registered the amended content must be attested
+    #[account(
+        seeds = [b"signing_key", provider.key().as_ref()],
+        bump,
+    )]
+    pub provider_signing_key: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, read to find the Ed25519 attestation
+    #[account(address = solana_sdk_ids::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        init,
+        payer = provider,
+        space = RecordVersion::LEN,
+        seeds = [
+            b"record_version",
+            record.key().as_ref(),
+            &record.version.to_le_bytes()
+        ],
+        bump
+    )]
+    pub record_version: Account<'info, RecordVersion>,
+
+    #[account(
+        init,
+        payer = provider,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(
+        mut,
+        constraint = provider.key() == record.created_by @ CypherMedError::Unauthorized
+    )]
+    pub provider: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RejectAmendment<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", patient.authority.as_ref()],
+        bump = patient.bump,
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"amendment",
+            record.key().as_ref(),
+            &amendment.index.to_le_bytes()
+        ],
+        bump = amendment.bump,
+    )]
+    pub amendment: Account<'info, AmendmentRequest>,
+
+    #[account(
+        init,
+        payer = provider,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(
+        mut,
+        constraint = provider.key() == record.created_by @ CypherMedError::Unauthorized
+    )]
+    pub provider: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FileDisagreement<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"amendment",
+            record.key().as_ref(),
+            &amendment.index.to_le_bytes()
+        ],
+        bump = amendment.bump,
+    )]
+    pub amendment: Account<'info, AmendmentRequest>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct AmendmentRequestedEvent {
+    pub record: Pubkey,
+    pub patient: Pubkey,
+    pub amendment: Pubkey,
+    pub index: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AmendmentResolvedEvent {
+    pub record: Pubkey,
+    pub amendment: Pubkey,
+    pub provider: Pubkey,
+    pub accepted: bool,
+    pub resulting_version: Option<u32>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisagreementFiledEvent {
+    pub record: Pubkey,
+    pub amendment: Pubkey,
+    pub patient: Pubkey,
+    pub timestamp: i64,
+}
@@ -1,15 +1,24 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::CypherMedError;
+use crate::audit::*;
+use crate::instructions::key_envelope::share_data_key;
 
 /// Approve an access request and create access grant
+///
+/// Supplying `wrapped_key` also hands the requester the patient's data key,
+/// so the new grantee can decrypt what it is allowed to view. The definition of
+/// every custom record type allowed is passed in `remaining_accounts`, in order.
+#[allow(clippy::too_many_arguments)]
 pub fn approve_access_request(
     ctx: Context<ApproveAccessRequest>,
-    allowed_record_types: Vec<RecordType>,
+    allowed_record_types: Vec<RecordTypeCode>,
     grant_expiration: Option<i64>,
     can_create: bool,
     can_modify: bool,
     can_view: bool,
+    can_delete: bool,
+    wrapped_key: Option<WrappedKey>,
 ) -> Result<()> {
     let patient = &mut ctx.accounts.patient;
     let access_request = &mut ctx.accounts.access_request;
@@ -43,9 +52,10 @@ pub fn approve_access_request(
         CypherMedError::NoRecordTypesSpecified
     );
     require!(
-        allowed_record_types.len() <= 7,
+        allowed_record_types.len() <= AccessGrant::MAX_RECORD_TYPES,
         CypherMedError::TooManyRecordTypes
     );
+    RecordTypeDefinition::require_grantable(&allowed_record_types, ctx.remaining_accounts)?;
 
     // Validate grant expiration if provided
     if let Some(exp) = grant_expiration {
@@ -71,15 +81,57 @@ pub fn approve_access_request(
     access_grant.can_create = can_create;
     access_grant.can_modify = can_modify;
     access_grant.can_view = can_view;
+    access_grant.can_delete = can_delete;
     access_grant.reason = access_request.reason.clone();
     access_grant.revoked_by = None;
     access_grant.revoked_at = None;
     access_grant.bump = ctx.bumps.access_grant;
 
+    let key_shared = wrapped_key.is_some();
+    access_grant.encryption_key_id = share_data_key(
+        ctx.accounts.key_envelope.as_mut(),
+        ctx.bumps.key_envelope,
+        ctx.accounts.grantee_encryption_key.as_mut(),
+        patient,
+        wrapped_key,
+        access_request.encryption_key_id,
+        clock.unix_timestamp,
+    )?;
+
     // Update patient stats
     patient.access_grant_count = patient.access_grant_count.checked_add(1).unwrap();
     patient.updated_at = clock.unix_timestamp;
 
+    // Log the approval in the audit trail
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::ApproveAccessRequest,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!(
+            "Approved access request from {}{}",
+            access_request.requester,
+            if key_shared { " (data key shared)" } else { "" }
+        )),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
     msg!(
         "Access request approved: {} granted access to patient {}",
         access_request.requester,
@@ -102,7 +154,7 @@ pub fn deny_access_request(
     denial_reason: Option<String>,
 ) -> Result<()> {
     let access_request = &mut ctx.accounts.access_request;
-    let patient = &ctx.accounts.patient;
+    let patient = &mut ctx.accounts.patient;
     let clock = Clock::get()?;
 
     // Verify patient is active
@@ -133,6 +185,33 @@ pub fn deny_access_request(
     access_request.responded_at = Some(clock.unix_timestamp);
     access_request.responded_by = Some(ctx.accounts.authority.key());
     access_request.denial_reason = denial_reason.clone();
+    patient.updated_at = clock.unix_timestamp;
+
+    // Log the denial in the audit trail
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::DenyAccessRequest,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Denied access request from {}", access_request.requester)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
 
     msg!(
         "Access request denied: {} denied access to patient {}",
@@ -180,6 +259,39 @@ pub struct ApproveAccessRequest<'info> {
     )]
     pub access_grant: Account<'info, AccessGrant>,
 
+    /// Grantee's registered encryption key (required when sharing the data key)
+    #[account(
+        mut,
+        seeds = [b"encryption_key", access_request.requester.as_ref()],
+        bump = grantee_encryption_key.bump,
+    )]
+    pub grantee_encryption_key: Option<Account<'info, EncryptionKey>>,
+
+    /// Envelope carrying the patient's data key to the grantee
+    #[account(
+        init,
+        payer = authority,
+        space = KeyEnvelope::LEN,
+        seeds = [b"key_envelope", patient.key().as_ref(), access_request.requester.as_ref()],
+        bump
+    )]
+    pub key_envelope: Option<Account<'info, KeyEnvelope>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
 
@@ -189,6 +301,7 @@ pub struct ApproveAccessRequest<'info> {
 #[derive(Accounts)]
 pub struct DenyAccessRequest<'info> {
     #[account(
+        mut,
         seeds = [b"patient", authority.key().as_ref()],
         bump = patient.bump,
         has_one = authority @ CypherMedError::Unauthorized
@@ -206,7 +319,25 @@ pub struct DenyAccessRequest<'info> {
     )]
     pub access_request: Account<'info, AccessRequest>,
 
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
     pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[event]
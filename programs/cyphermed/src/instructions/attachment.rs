@@ -0,0 +1,293 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::CypherMedError;
+use crate::audit::*;
+use crate::authorization::*;
+
+/// Attach an additional blob to a medical record
+///
+/// Requires the same rights as `update_record`. The record's version is not
+/// bumped; attachments are tracked by their own accounts.
+pub fn add_attachment(
+    ctx: Context<AddAttachment>,
+    storage: StorageLocator,
+    data_hash: ContentHash,
+    mime_type: MimeType,
+    size_bytes: u64,
+) -> Result<()> {
+    require!(size_bytes > 0, CypherMedError::InvalidAttachmentSize);
+    storage.validate(ctx.accounts.config.as_deref())?;
+
+    let patient = &mut ctx.accounts.patient;
+    let record = &mut ctx.accounts.record;
+    let clock = Clock::get()?;
+
+    let auth = authorize(
+        patient,
+        &ctx.accounts.editor.key(),
+        ctx.accounts.access_grant.as_deref(),
+        RecordOperation::Modify,
+        RecordTarget::Existing(record),
+        clock.unix_timestamp,
+    );
+    auth.decision.require_allowed()?;
+
+    let attachment = &mut ctx.accounts.attachment;
+    attachment.record = record.key();
+    attachment.index = record.next_attachment_index;
+    attachment.storage = storage;
+    attachment.data_hash = data_hash;
+    attachment.mime_type = mime_type;
+    attachment.size_bytes = size_bytes;
+    attachment.added_by = ctx.accounts.editor.key();
+    attachment.added_at = clock.unix_timestamp;
+    attachment.bump = ctx.bumps.attachment;
+
+    record.next_attachment_index = record.next_attachment_index.checked_add(1).unwrap();
+    record.attachment_count = record.attachment_count.checked_add(1).unwrap();
+    record.modified_at = clock.unix_timestamp;
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.editor.key(),
+        accessor_role: auth.role,
+        action: AccessAction::AddAttachment,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!(
+            "Attachment #{} added ({:?}, {} bytes)",
+            attachment.index, mime_type, size_bytes
+        )),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!("Attachment #{} added to record {}", attachment.index, record.key());
+
+    emit!(AttachmentAddedEvent {
+        record: record.key(),
+        attachment: attachment.key(),
+        index: attachment.index,
+        mime_type,
+        size_bytes,
+        added_by: attachment.added_by,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Remove an attachment from a medical record, refunding its rent to whoever added it
+pub fn remove_attachment(ctx: Context<RemoveAttachment>) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let record = &mut ctx.accounts.record;
+    let attachment = &ctx.accounts.attachment;
+    let clock = Clock::get()?;
+
+    let auth = authorize(
+        patient,
+        &ctx.accounts.editor.key(),
+        ctx.accounts.access_grant.as_deref(),
+        RecordOperation::Modify,
+        RecordTarget::Existing(record),
+        clock.unix_timestamp,
+    );
+    auth.decision.require_allowed()?;
+
+    record.attachment_count = record.attachment_count.checked_sub(1).unwrap();
+    record.modified_at = clock.unix_timestamp;
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.editor.key(),
+        accessor_role: auth.role,
+        action: AccessAction::RemoveAttachment,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Attachment #{} removed", attachment.index)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!("Attachment #{} removed from record {}", attachment.index, record.key());
+
+    emit!(AttachmentRemovedEvent {
+        record: record.key(),
+        attachment: attachment.key(),
+        index: attachment.index,
+        removed_by: ctx.accounts.editor.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AddAttachment<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", patient.authority.as_ref()],
+        bump = patient.bump,
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        mut,
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    /// Optional access grant (if editor is not patient or creator)
+    #[account(
+        seeds = [b"access_grant", patient.key().as_ref(), editor.key().as_ref()],
+        bump,
+    )]
+    pub access_grant: Option<Account<'info, AccessGrant>>,
+
+    /// Program config (required only for custom storage schemes)
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Option<Account<'info, ProgramConfig>>,
+
+    #[account(
+        init,
+        payer = editor,
+        space = RecordAttachment::LEN,
+        seeds = [
+            b"attachment",
+            record.key().as_ref(),
+            &record.next_attachment_index.to_le_bytes()
+        ],
+        bump
+    )]
+    pub attachment: Account<'info, RecordAttachment>,
+
+    #[account(
+        init,
+        payer = editor,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub editor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAttachment<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", patient.authority.as_ref()],
+        bump = patient.bump,
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        mut,
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    /// Optional access grant (if editor is not patient or creator)
+    #[account(
+        seeds = [b"access_grant", patient.key().as_ref(), editor.key().as_ref()],
+        bump,
+    )]
+    pub access_grant: Option<Account<'info, AccessGrant>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"attachment",
+            record.key().as_ref(),
+            &attachment.index.to_le_bytes()
+        ],
+        bump = attachment.bump,
+        close = added_by,
+    )]
+    pub attachment: Account<'info, RecordAttachment>,
+
+    /// Receives the attachment's rent
+    #[account(mut, address = attachment.added_by)]
+    pub added_by: SystemAccount<'info>,
+
+    #[account(
+        init,
+        payer = editor,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub editor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct AttachmentAddedEvent {
+    pub record: Pubkey,
+    pub attachment: Pubkey,
+    pub index: u32,
+    pub mime_type: MimeType,
+    pub size_bytes: u64,
+    pub added_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AttachmentRemovedEvent {
+    pub record: Pubkey,
+    pub attachment: Pubkey,
+    pub index: u32,
+    pub removed_by: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::CypherMedError;
+use crate::audit::*;
+
+/// Initialize the patient's compressed audit tree
+///
+/// The tree account must already be allocated (owned by the account
+/// compression program) with space for `max_depth` / `max_buffer_size`.
+pub fn initialize_audit_tree(
+    ctx: Context<InitializeAuditTree>,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let clock = Clock::get()?;
+
+    require!(patient.is_active, CypherMedError::PatientInactive);
+    require!(
+        patient.audit_tree.is_none(),
+        CypherMedError::AuditTreeAlreadyInitialized
+    );
+
+    init_audit_tree(
+        patient,
+        &ctx.accounts.audit_tree.to_account_info(),
+        &ctx.accounts.compression_program.to_account_info(),
+        &ctx.accounts.noop_program.to_account_info(),
+        max_depth,
+        max_buffer_size,
+    )?;
+
+    patient.audit_tree = Some(ctx.accounts.audit_tree.key());
+    patient.updated_at = clock.unix_timestamp;
+
+    msg!(
+        "Compressed audit tree {} initialized for patient {}",
+        ctx.accounts.audit_tree.key(),
+        patient.key()
+    );
+    
+    Ok(())
+}
+
+/// Choose where new audit entries for this patient are written
+pub fn set_audit_mode(
+    ctx: Context<SetAuditMode>,
+    mode: AuditMode,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let clock = Clock::get()?;
+
+    require!(patient.is_active, CypherMedError::PatientInactive);
+    if mode == AuditMode::Compressed {
+        require!(
+            patient.audit_tree.is_some(),
+            CypherMedError::AuditTreeNotInitialized
+        );
+    }
+
+    patient.audit_mode = mode;
+    patient.updated_at = clock.unix_timestamp;
+
+    msg!("Patient {} audit mode set to {:?}", patient.key(), mode);
+    
+    emit!(AuditModeChangedEvent {
+        patient: patient.key(),
+        mode,
+        audit_tree: patient.audit_tree,
+        next_sequence: patient.audit_count,
+        timestamp: clock.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeAuditTree<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    /// CHECK: Pre-allocated tree account, initialized by account compression
+    #[account(mut, owner = ACCOUNT_COMPRESSION_ID)]
+    pub audit_tree: UncheckedAccount<'info>,
+
+    /// CHECK: Account compression program
+    #[account(address = ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: Noop program
+    #[account(address = NOOP_ID)]
+    pub noop_program: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAuditMode<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct AuditModeChangedEvent {
+    pub patient: Pubkey,
+    pub mode: AuditMode,
+    pub audit_tree: Option<Pubkey>,
+    pub next_sequence: u64,
+    pub timestamp: i64,
+}
@@ -1,17 +1,20 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::CypherMedError;
+use crate::audit::*;
 
 /// Batch grant access to multiple providers at once
+#[allow(clippy::too_many_arguments)]
 pub fn batch_grant_access(
     ctx: Context<BatchGrantAccess>,
     providers: Vec<Pubkey>,
     roles: Vec<Role>,
-    allowed_record_types: Vec<RecordType>,
+    allowed_record_types: Vec<RecordTypeCode>,
     expires_at: Option<i64>,
     _can_create: bool,
     _can_modify: bool,
     _can_view: bool,
+    _can_delete: bool,
     _reason: Option<String>,
 ) -> Result<()> {
     let patient = &mut ctx.accounts.patient;
@@ -28,6 +31,10 @@ pub fn batch_grant_access(
         !allowed_record_types.is_empty(),
         CypherMedError::NoRecordTypesSpecified
     );
+    require!(
+        allowed_record_types.len() <= AccessGrant::MAX_RECORD_TYPES,
+        CypherMedError::TooManyRecordTypes
+    );
 
     // Validate expiration time if provided
     if let Some(expiry) = expires_at {
@@ -38,12 +45,15 @@ pub fn batch_grant_access(
     }
 
     // This instruction creates accounts via remaining_accounts
-    // Each access_grant PDA must be passed in remaining_accounts
+    // Each access_grant PDA must be passed in remaining_accounts, followed
+    // by the definition of each custom record type
     let remaining = &ctx.remaining_accounts;
     require!(
-        remaining.len() == providers.len(),
+        remaining.len() >= providers.len(),
         CypherMedError::InvalidRemainingAccounts
     );
+    let (_grants, definitions) = remaining.split_at(providers.len());
+    RecordTypeDefinition::require_grantable(&allowed_record_types, definitions)?;
 
     msg!(
         "Batch granting access to {} providers for patient {}",
@@ -69,6 +79,32 @@ pub fn batch_grant_access(
     });
 
     patient.updated_at = clock.unix_timestamp;
+
+    // Log the batch grant in the audit trail
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::BatchGrantAccess,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Batch granted access to {} providers", providers.len())),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
     
     Ok(())
 }
@@ -83,6 +119,21 @@ pub struct BatchGrantAccess<'info> {
     )]
     pub patient: Account<'info, Patient>,
 
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
 
@@ -94,7 +145,7 @@ pub struct BatchAccessGrantedEvent {
     pub patient: Pubkey,
     pub providers: Vec<Pubkey>,
     pub roles: Vec<Role>,
-    pub record_types: Vec<RecordType>,
+    pub record_types: Vec<RecordTypeCode>,
     pub granted_by: Pubkey,
     pub timestamp: i64,
 }
@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::authorization::*;
+
+/// Dry-run of `access_record`: evaluates the same authorization logic for
+/// `accessor` without writing anything, and returns the verdict as return
+/// data. The accessor does not sign, so other programs can query on behalf
+/// of any subject via CPI.
+pub fn check_access(ctx: Context<CheckAccess>) -> Result<AccessCheck> {
+    let clock = Clock::get()?;
+    let grant = ctx.accounts.access_grant.as_deref();
+
+    let Authorization { decision, role: effective_role } = authorize(
+        &ctx.accounts.patient,
+        &ctx.accounts.accessor.key(),
+        grant,
+        RecordOperation::View,
+        RecordTarget::Existing(&ctx.accounts.record),
+        clock.unix_timestamp,
+    );
+
+    // Only a grant that would actually be used carries a remaining window
+    let is_patient = ctx.accounts.accessor.key() == ctx.accounts.patient.authority;
+    let remaining_quota = match grant {
+        Some(grant) if decision.is_allowed() && !is_patient => grant
+            .expires_at
+            .map(|expires_at| expires_at.saturating_sub(clock.unix_timestamp)),
+        _ => None,
+    };
+
+    Ok(AccessCheck {
+        allowed: decision.is_allowed(),
+        decision,
+        effective_role,
+        remaining_quota,
+    })
+}
+
+#[derive(Accounts)]
+pub struct CheckAccess<'info> {
+    #[account(
+        seeds = [b"patient", patient.authority.as_ref()],
+        bump = patient.bump,
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    /// Optional access grant (required if accessor is not the patient)
+    #[account(
+        seeds = [b"access_grant", patient.key().as_ref(), accessor.key().as_ref()],
+        bump,
+    )]
+    pub access_grant: Option<Account<'info, AccessGrant>>,
+
+    /// CHECK: Subject being evaluated; only its address is used
+    pub accessor: UncheckedAccount<'info>,
+}
+
+/// Result of a `check_access` call, returned via return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AccessCheck {
+    pub allowed: bool,
+    pub decision: AccessDecision,
+    /// Role the accessor would act under (`Patient` for the record owner)
+    pub effective_role: Role,
+    /// Seconds left on the grant; `None` for the owner, denials, and
+    /// grants without an expiry
+    pub remaining_quota: Option<i64>,
+}
@@ -1,93 +1,130 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::CypherMedError;
+use crate::audit::*;
 use crate::utils::*;
+use crate::authorization::*;
+use crate::attestation::attest_record;
 
 /// Create a new medical record
 pub fn create_record(
     ctx: Context<CreateRecord>,
     record_id: String,
-    record_type: RecordType,
-    data_hash: String,
-    storage_cid: Option<String>,
+    record_type: RecordTypeCode,
+    data_hash: ContentHash,
+    storage: Option<StorageLocator>,
     metadata: Option<String>,
+    clinical_codes: Vec<ClinicalCode>,
 ) -> Result<()> {
     // Validate record_id length
     require!(
         validate_string_length(&record_id, 64),
         CypherMedError::RecordIdTooLong
     );
+    if let Some(ref storage) = storage {
+        storage.validate(ctx.accounts.config.as_deref())?;
+    }
+    ClinicalCode::validate_list(&clinical_codes)?;
+    require!(ctx.accounts.tombstone.data_is_empty(), CypherMedError::RecordIdPurged);
+    let definition = RecordTypeDefinition::load(&ctx.accounts.record_type_definition)?;
+    RecordTypeDefinition::require_usable(record_type, definition.as_ref())?;
 
     let patient = &mut ctx.accounts.patient;
     let record = &mut ctx.accounts.record;
     let clock = Clock::get()?;
 
-    // Verify patient is active
-    require!(patient.is_active, CypherMedError::PatientInactive);
-
     // Check if provider is authorized (either the patient or has access grant)
-    let is_patient = ctx.accounts.provider.key() == patient.authority;
-    
-    if !is_patient {
-        let access_grant = &ctx.accounts.access_grant.as_ref()
-            .ok_or(CypherMedError::AccessDenied)?;
-        
-        require!(access_grant.is_active, CypherMedError::AccessGrantRevoked);
-        require!(
-            !is_grant_expired(access_grant.expires_at, clock.unix_timestamp),
-            CypherMedError::AccessGrantExpired
-        );
-        require!(access_grant.can_create, CypherMedError::AccessDenied);
-        require!(
-            access_grant.allowed_record_types.contains(&record_type),
-            CypherMedError::AccessDenied
-        );
-    }
+    let auth = authorize(
+        patient,
+        &ctx.accounts.provider.key(),
+        ctx.accounts.access_grant.as_deref(),
+        RecordOperation::Create,
+        RecordTarget::New(record_type),
+        clock.unix_timestamp,
+    );
+    auth.decision.require_allowed()?;
 
     record.patient = patient.key();
     record.created_by = ctx.accounts.provider.key();
+    record.created_by_role = auth.role;
     record.record_type = record_type;
     record.record_id = record_id;
     record.data_hash = data_hash;
-    record.storage_cid = storage_cid;
+    record.storage = storage;
     record.created_at = clock.unix_timestamp;
     record.modified_at = clock.unix_timestamp;
+    record.content_updated_at = clock.unix_timestamp;
     record.last_accessed = clock.unix_timestamp;
     record.access_count = 0;
     record.is_active = true;
+    record.deleted_by = None;
+    record.deleted_at = None;
+    record.retention_flagged_at = None;
     record.metadata = metadata;
+    record.version = 1;
+    record.amendment_count = 0;
+    record.attested = false;
+    record.attested_by = None;
+    record.key_epoch = patient.data_key_epoch;
+    record.needs_rekey = false;
+    record.attachment_count = 0;
+    record.next_attachment_index = 0;
+    record.clinical_codes = clinical_codes;
     record.bump = ctx.bumps.record;
 
+    attest_record(
+        record,
+        &ctx.accounts.provider_signing_key,
+        ctx.accounts.instructions_sysvar.as_ref(),
+        clock.unix_timestamp,
+    )?;
+
     // Update patient record count
     patient.record_count = patient.record_count.checked_add(1).unwrap();
     patient.updated_at = clock.unix_timestamp;
 
     // Log the creation in audit log
-    let audit = &mut ctx.accounts.audit_log;
-    audit.patient = patient.key();
-    audit.record = record.key();
-    audit.accessor = ctx.accounts.provider.key();
-    audit.accessor_role = if is_patient { Role::Patient } else { 
-        ctx.accounts.access_grant.as_ref().unwrap().role 
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.provider.key(),
+        accessor_role: auth.role,
+        action: AccessAction::Create,
+        record_type: Some(record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some("Record created".to_string()),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
     };
-    audit.action = AccessAction::Create;
-    audit.record_type = record_type;
-    audit.timestamp = clock.unix_timestamp;
-    audit.success = true;
-    audit.failure_reason = None;
-    audit.is_emergency = false;
-    audit.emergency_justification = None;
-    audit.client_info = None;
-    audit.metadata = Some("Record created".to_string());
-    audit.bump = ctx.bumps.audit_log;
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
 
     msg!("Medical record created: {} for patient: {}", record.key(), patient.key());
+
+    emit!(RecordCreatedEvent {
+        record: record.key(),
+        patient: patient.key(),
+        created_by: record.created_by,
+        record_type,
+        clinical_codes: record.clinical_codes.clone(),
+        timestamp: clock.unix_timestamp,
+    });
     
     Ok(())
 }
 
 #[derive(Accounts)]
-#[instruction(record_id: String)]
+#[instruction(record_id: String, record_type: RecordTypeCode)]
 pub struct CreateRecord<'info> {
     #[account(
         mut,
@@ -105,6 +142,13 @@ pub struct CreateRecord<'info> {
     )]
     pub record: Account<'info, MedicalRecord>,
 
+    /// CHECK: Tombstone PDA for the record; must be empty (a purged id is never reused)
+    #[account(
+        seeds = [b"tombstone", record.key().as_ref()],
+        bump,
+    )]
+    pub tombstone: UncheckedAccount<'info>,
+
     /// Optional access grant (required if provider is not the patient)
     #[account(
         seeds = [b"access_grant", patient.key().as_ref(), provider.key().as_ref()],
@@ -112,23 +156,58 @@ pub struct CreateRecord<'info> {
     )]
     pub access_grant: Option<Account<'info, AccessGrant>>,
 
+    /// CHECK: Definition PDA for `record_type`; may be empty for a built-in type
+    #[account(
+        seeds = [b"record_type".as_ref(), &record_type.to_le_bytes()],
+        bump,
+    )]
+    pub record_type_definition: UncheckedAccount<'info>,
+
+    /// Program config (required only for custom storage schemes)
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Option<Account<'info, ProgramConfig>>,
+
+    /// CHECK: Signing key PDA of the provider; if a key is registered the content must be attested
+    #[account(
+        seeds = [b"signing_key", provider.key().as_ref()],
+        bump,
+    )]
+    pub provider_signing_key: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, read to find the Ed25519 attestation
+    #[account(address = solana_sdk_ids::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
     #[account(
         init,
         payer = provider,
         space = AuditLog::LEN,
         seeds = [
-            b"audit", 
-            record.key().as_ref(), 
-            provider.key().as_ref(), 
-            b"create",
-            &patient.record_count.to_le_bytes()
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
         ],
         bump
     )]
-    pub audit_log: Account<'info, AuditLog>,
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
 
     #[account(mut)]
     pub provider: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
+
+#[event]
+pub struct RecordCreatedEvent {
+    pub record: Pubkey,
+    pub patient: Pubkey,
+    pub created_by: Pubkey,
+    pub record_type: RecordTypeCode,
+    pub clinical_codes: Vec<ClinicalCode>,
+    pub timestamp: i64,
+}
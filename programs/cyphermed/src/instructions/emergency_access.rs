@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::CypherMedError;
+use crate::audit::*;
+use crate::instructions::key_escrow::EscrowReleaseOpenedEvent;
 
 /// Emergency access to medical records (break-glass scenario)
 pub fn emergency_access(
@@ -8,7 +10,7 @@ pub fn emergency_access(
     justification: String,
     client_info: Option<String>,
 ) -> Result<()> {
-    let patient = &ctx.accounts.patient;
+    let patient = &mut ctx.accounts.patient;
     let record = &mut ctx.accounts.record;
     let clock = Clock::get()?;
 
@@ -22,26 +24,107 @@ pub fn emergency_access(
     require!(patient.is_active, CypherMedError::PatientInactive);
     require!(record.is_active, CypherMedError::RecordInactive);
 
+    // Enforce per-responder rate limits within the sliding window
+    let config = &ctx.accounts.config;
+    let rate_limit = &mut ctx.accounts.rate_limit;
+    let responder = ctx.accounts.emergency_responder.key();
+    let window = config.emergency_window_seconds;
+
+    rate_limit.roll_window(clock.unix_timestamp, window);
+
+    let attempts = rate_limit.attempts_in_window(clock.unix_timestamp, window);
+    require!(
+        attempts < config.emergency_hard_cap as u64,
+        CypherMedError::EmergencyRateLimitExceeded
+    );
+
+    // Past the soft threshold a second, registered and unflagged responder must co-sign
+    let co_signer = ctx.accounts.co_signer.as_ref().map(|s| s.key());
+    if attempts >= config.emergency_soft_threshold as u64 {
+        let co_signer = co_signer.ok_or(CypherMedError::EmergencyCoSignatureRequired)?;
+        require!(co_signer != responder, CypherMedError::InvalidCoSigner);
+
+        let co_signer_limit = ctx
+            .accounts
+            .co_signer_rate_limit
+            .as_ref()
+            .ok_or(CypherMedError::InvalidCoSigner)?;
+        require_keys_eq!(co_signer_limit.responder, co_signer, CypherMedError::InvalidCoSigner);
+        require!(co_signer_limit.registered, CypherMedError::InvalidCoSigner);
+        require!(
+            !co_signer_limit.is_flagged(clock.unix_timestamp, window),
+            CypherMedError::InvalidCoSigner
+        );
+    }
+
+    rate_limit.current_window_count = rate_limit.current_window_count.checked_add(1).unwrap();
+    rate_limit.total_count = rate_limit.total_count.checked_add(1).unwrap();
+
+    if attempts + 1 == config.emergency_soft_threshold as u64 {
+        rate_limit.flagged_at = Some(clock.unix_timestamp);
+
+        msg!(
+            "⚠️ EMERGENCY ANOMALY: Responder {} reached {} break-glass attempts in {}s",
+            responder,
+            attempts + 1,
+            window
+        );
+
+        emit!(EmergencyAnomalyEvent {
+            responder,
+            attempts_in_window: attempts + 1,
+            soft_threshold: config.emergency_soft_threshold,
+            window_seconds: window,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    // A valid break-glass lets the escrow custodians release their shares
+    if let Some(escrow) = ctx.accounts.key_escrow.as_mut() {
+        escrow.open_release(responder, patient.data_key_epoch, clock.unix_timestamp)?;
+
+        msg!("Key escrow release window opened for {}", responder);
+
+        emit!(EscrowReleaseOpenedEvent {
+            patient: patient.key(),
+            responder,
+            closes_at: clock.unix_timestamp.saturating_add(KeyEscrow::RELEASE_WINDOW_SECONDS),
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
     // Update record access statistics
     record.last_accessed = clock.unix_timestamp;
     record.access_count = record.access_count.checked_add(1).unwrap();
 
     // Create audit log entry for emergency access
-    let audit = &mut ctx.accounts.audit_log;
-    audit.patient = patient.key();
-    audit.record = record.key();
-    audit.accessor = ctx.accounts.emergency_responder.key();
-    audit.accessor_role = Role::EmergencyResponder;
-    audit.action = AccessAction::EmergencyAccess;
-    audit.record_type = record.record_type;
-    audit.timestamp = clock.unix_timestamp;
-    audit.success = true;
-    audit.failure_reason = None;
-    audit.is_emergency = true;
-    audit.emergency_justification = Some(justification.clone());
-    audit.client_info = client_info;
-    audit.metadata = Some("EMERGENCY ACCESS - Break-glass protocol activated".to_string());
-    audit.bump = ctx.bumps.audit_log;
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.emergency_responder.key(),
+        accessor_role: Role::EmergencyResponder,
+        action: AccessAction::EmergencyAccess,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: true,
+        emergency_justification: Some(justification.clone()),
+        client_info,
+        metadata: Some(match co_signer {
+            Some(co_signer) => format!("EMERGENCY ACCESS - co-signed by {}", co_signer),
+            None => "EMERGENCY ACCESS - Break-glass protocol activated".to_string(),
+        }),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
 
     msg!(
         "⚠️ EMERGENCY ACCESS: Record {} accessed by {} | Reason: {}",
@@ -57,6 +140,7 @@ pub fn emergency_access(
         responder: ctx.accounts.emergency_responder.key(),
         timestamp: clock.unix_timestamp,
         justification,
+        co_signer,
     });
     
     Ok(())
@@ -65,6 +149,7 @@ pub fn emergency_access(
 #[derive(Accounts)]
 pub struct EmergencyAccess<'info> {
     #[account(
+        mut,
         seeds = [b"patient", patient.authority.as_ref()],
         bump = patient.bump,
     )]
@@ -82,19 +167,47 @@ pub struct EmergencyAccess<'info> {
         payer = emergency_responder,
         space = AuditLog::LEN,
         seeds = [
-            b"audit", 
-            record.key().as_ref(), 
-            emergency_responder.key().as_ref(), 
-            b"emergency",
-            &record.access_count.to_le_bytes()
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
         ],
         bump
     )]
-    pub audit_log: Account<'info, AuditLog>,
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"emergency_rate", emergency_responder.key().as_ref()],
+        bump = rate_limit.bump,
+        constraint = rate_limit.registered @ CypherMedError::ResponderNotRegistered,
+    )]
+    pub rate_limit: Account<'info, EmergencyRateLimit>,
+
+    /// Patient's key escrow, to let custodians release their shares
+    #[account(
+        mut,
+        seeds = [b"key_escrow", patient.key().as_ref()],
+        bump = key_escrow.bump,
+    )]
+    pub key_escrow: Option<Account<'info, KeyEscrow>>,
 
     #[account(mut)]
     pub emergency_responder: Signer<'info>,
 
+    /// Second responder (required once the soft threshold is crossed)
+    pub co_signer: Option<Signer<'info>>,
+
+    /// Co-signer's registry entry, proving it is a registered, unflagged responder
+    pub co_signer_rate_limit: Option<Account<'info, EmergencyRateLimit>>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -105,4 +218,14 @@ pub struct EmergencyAccessEvent {
     pub responder: Pubkey,
     pub timestamp: i64,
     pub justification: String,
+    pub co_signer: Option<Pubkey>,
+}
+
+#[event]
+pub struct EmergencyAnomalyEvent {
+    pub responder: Pubkey,
+    pub attempts_in_window: u64,
+    pub soft_threshold: u32,
+    pub window_seconds: i64,
+    pub timestamp: i64,
 }
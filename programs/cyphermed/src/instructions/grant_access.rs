@@ -1,17 +1,26 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::CypherMedError;
+use crate::audit::*;
+use crate::instructions::key_envelope::share_data_key;
 
 /// Grant access to a healthcare provider
+///
+/// Each allowed record type must be built in or registered; pass the
+/// definition of every custom type in `remaining_accounts`, in order.
+#[allow(clippy::too_many_arguments)]
 pub fn grant_access(
     ctx: Context<GrantAccess>,
     role: Role,
-    allowed_record_types: Vec<RecordType>,
+    allowed_record_types: Vec<RecordTypeCode>,
     expires_at: Option<i64>,
     can_create: bool,
     can_modify: bool,
     can_view: bool,
+    can_delete: bool,
     reason: Option<String>,
+    wrapped_key: Option<WrappedKey>,
+    encryption_key_id: Option<u32>,
 ) -> Result<()> {
     let patient = &mut ctx.accounts.patient;
     let access_grant = &mut ctx.accounts.access_grant;
@@ -32,9 +41,10 @@ pub fn grant_access(
         CypherMedError::NoRecordTypesSpecified
     );
     require!(
-        allowed_record_types.len() <= 7, // Max all record types
+        allowed_record_types.len() <= AccessGrant::MAX_RECORD_TYPES,
         CypherMedError::TooManyRecordTypes
     );
+    RecordTypeDefinition::require_grantable(&allowed_record_types, ctx.remaining_accounts)?;
 
     // Validate expiration time if provided
     if let Some(expiry) = expires_at {
@@ -54,15 +64,58 @@ pub fn grant_access(
     access_grant.can_create = can_create;
     access_grant.can_modify = can_modify;
     access_grant.can_view = can_view;
+    access_grant.can_delete = can_delete;
     access_grant.reason = reason;
     access_grant.revoked_by = None;
     access_grant.revoked_at = None;
     access_grant.bump = ctx.bumps.access_grant;
 
+    let key_shared = wrapped_key.is_some();
+    access_grant.encryption_key_id = share_data_key(
+        ctx.accounts.key_envelope.as_mut(),
+        ctx.bumps.key_envelope,
+        ctx.accounts.grantee_encryption_key.as_mut(),
+        patient,
+        wrapped_key,
+        encryption_key_id,
+        clock.unix_timestamp,
+    )?;
+
     // Update patient access grant count
     patient.access_grant_count = patient.access_grant_count.checked_add(1).unwrap();
     patient.updated_at = clock.unix_timestamp;
 
+    // Log the grant in the audit trail
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::GrantAccess,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!(
+            "Granted {:?} access to {}{}",
+            role,
+            ctx.accounts.provider.key(),
+            if key_shared { " (data key shared)" } else { "" }
+        )),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
     msg!(
         "Access granted to provider: {} by patient: {} with role: {:?}",
         ctx.accounts.provider.key(),
@@ -92,6 +145,39 @@ pub struct GrantAccess<'info> {
     )]
     pub access_grant: Account<'info, AccessGrant>,
 
+    /// Grantee's registered encryption key (required when sharing the data key)
+    #[account(
+        mut,
+        seeds = [b"encryption_key", provider.key().as_ref()],
+        bump = grantee_encryption_key.bump,
+    )]
+    pub grantee_encryption_key: Option<Account<'info, EncryptionKey>>,
+
+    /// Envelope carrying the patient's data key to the grantee
+    #[account(
+        init,
+        payer = authority,
+        space = KeyEnvelope::LEN,
+        seeds = [b"key_envelope", patient.key().as_ref(), provider.key().as_ref()],
+        bump
+    )]
+    pub key_envelope: Option<Account<'info, KeyEnvelope>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
     /// CHECK: The provider receiving access (verified in seeds)
     pub provider: AccountInfo<'info>,
 
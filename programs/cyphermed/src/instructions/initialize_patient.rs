@@ -28,6 +28,12 @@ pub fn initialize_patient(
     patient.access_grant_count = 0;
     patient.is_active = true;
     patient.emergency_contact = emergency_contact;
+    patient.audit_count = 0;
+    patient.audit_head_hash = [0; 32];
+    patient.audit_mode = AuditMode::Account;
+    patient.audit_tree = None;
+    patient.deletion_cosign_window = None;
+    patient.data_key_epoch = 0;
     patient.bump = ctx.bumps.patient;
 
     msg!("Patient account initialized for: {}", patient.authority);
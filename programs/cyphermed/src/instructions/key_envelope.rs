@@ -0,0 +1,418 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::CypherMedError;
+use crate::audit::*;
+use crate::attestation::{encryption_key_binding_message, verify_ed25519_attestation};
+
+/// Register a new X25519 public key for this wallet, rotating out the current one
+///
+/// The instruction must be preceded by an Ed25519 verify instruction in which
+/// the wallet signs `encryption_key_binding_message(owner, next_key_id, key)`.
+/// Envelopes already issued stay wrapped to the key they name; the patient
+/// re-issues them after a rotation.
+pub fn register_encryption_key(
+    ctx: Context<RegisterEncryptionKey>,
+    x25519_public_key: [u8; 32],
+) -> Result<()> {
+    let registration = &mut ctx.accounts.encryption_key;
+    let owner = ctx.accounts.owner.key();
+    let clock = Clock::get()?;
+
+    let key_id = registration.next_key_id;
+    let message = encryption_key_binding_message(&owner, key_id, &x25519_public_key);
+    let binding_signature = verify_ed25519_attestation(
+        &ctx.accounts.instructions_sysvar,
+        &owner,
+        &message,
+    )?;
+
+    // Rotate out the current key and make room for the new one
+    let mut rotated_key_id = None;
+    for key in registration.keys.iter_mut().filter(|key| key.status == KeyStatus::Active) {
+        key.status = KeyStatus::Rotated;
+        key.status_changed_at = Some(clock.unix_timestamp);
+        rotated_key_id = Some(key.key_id);
+    }
+    registration.make_room()?;
+
+    registration.owner = owner;
+    registration.keys.push(X25519Key {
+        key_id,
+        public_key: x25519_public_key,
+        binding_signature,
+        created_at: clock.unix_timestamp,
+        status: KeyStatus::Active,
+        status_changed_at: None,
+        envelope_count: 0,
+    });
+    registration.next_key_id = key_id.checked_add(1).unwrap();
+    registration.bump = ctx.bumps.encryption_key;
+
+    msg!("Encryption key #{} registered for {}", key_id, owner);
+
+    emit!(EncryptionKeyRegisteredEvent {
+        owner,
+        key_id,
+        x25519_public_key,
+        rotated_key_id,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Revoke one of this wallet's encryption keys (e.g. after a compromise)
+///
+/// Envelopes wrapped to the key are closed, refunding the patients that paid
+/// for them. `remaining_accounts` holds (key envelope, patient authority)
+/// pairs; revoking an already revoked key again closes any envelopes left.
+pub fn revoke_encryption_key(
+    ctx: Context<RevokeEncryptionKey>,
+    key_id: u32,
+) -> Result<()> {
+    let registration = &mut ctx.accounts.encryption_key;
+    let clock = Clock::get()?;
+
+    let key = registration
+        .keys
+        .iter_mut()
+        .find(|key| key.key_id == key_id)
+        .ok_or(CypherMedError::EncryptionKeyNotFound)?;
+    if key.status == KeyStatus::Revoked {
+        require!(
+            !ctx.remaining_accounts.is_empty(),
+            CypherMedError::EncryptionKeyAlreadyRevoked
+        );
+    } else {
+        key.status = KeyStatus::Revoked;
+        key.status_changed_at = Some(clock.unix_timestamp);
+    }
+
+    require!(
+        ctx.remaining_accounts.len() % 2 == 0,
+        CypherMedError::InvalidRemainingAccounts
+    );
+    for pair in ctx.remaining_accounts.chunks(2) {
+        let (envelope, patient_authority) = (&pair[0], &pair[1]);
+        require_keys_eq!(*envelope.owner, crate::ID, CypherMedError::InvalidKeyEnvelope);
+        let sealed = {
+            let data = envelope.try_borrow_data()?;
+            KeyEnvelope::try_deserialize(&mut &data[..])?
+        };
+        require_keys_eq!(sealed.grantee, registration.owner, CypherMedError::InvalidKeyEnvelope);
+        require!(sealed.recipient_key_id == key_id, CypherMedError::InvalidKeyEnvelope);
+        let (patient, _) = Pubkey::find_program_address(
+            &[b"patient", patient_authority.key.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(patient, sealed.patient, CypherMedError::InvalidKeyEnvelope);
+
+        close_key_envelope(envelope, patient_authority, Some(registration))?;
+    }
+    let envelopes_closed = (ctx.remaining_accounts.len() / 2) as u32;
+    let envelopes_outstanding = EncryptionKey::key(registration, key_id).map_or(0, |key| key.envelope_count);
+
+    msg!(
+        "Encryption key #{} of {} revoked; {} envelopes closed, {} outstanding",
+        key_id,
+        registration.owner,
+        envelopes_closed,
+        envelopes_outstanding
+    );
+
+    emit!(EncryptionKeyRevokedEvent {
+        owner: registration.owner,
+        key_id,
+        envelopes_closed,
+        envelopes_outstanding,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Share (or re-share) the patient's data key with an existing grantee
+pub fn issue_key_envelope(
+    ctx: Context<IssueKeyEnvelope>,
+    wrapped_key: WrappedKey,
+    encryption_key_id: Option<u32>,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let access_grant = &mut ctx.accounts.access_grant;
+    let clock = Clock::get()?;
+
+    require!(patient.is_active, CypherMedError::PatientInactive);
+    require!(access_grant.is_active, CypherMedError::AccessGrantRevoked);
+
+    let recipient = &mut ctx.accounts.grantee_encryption_key;
+    let key = *recipient.usable_key(encryption_key_id)?;
+    seal_key_envelope(
+        &mut ctx.accounts.key_envelope,
+        ctx.bumps.key_envelope,
+        recipient,
+        &key,
+        patient,
+        wrapped_key,
+        clock.unix_timestamp,
+    )?;
+    access_grant.encryption_key_id = Some(key.key_id);
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::IssueKeyEnvelope,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!(
+            "Data key shared with {} (key #{})",
+            access_grant.provider, key.key_id
+        )),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    Ok(())
+}
+
+/// Fill in a key envelope for one of `recipient`'s keys and announce it
+///
+/// Re-issuing an envelope moves it from the key it was wrapped to before.
+pub(crate) fn seal_key_envelope(
+    envelope: &mut Account<KeyEnvelope>,
+    envelope_bump: u8,
+    recipient: &mut EncryptionKey,
+    key: &X25519Key,
+    patient: &Account<Patient>,
+    wrapped_key: WrappedKey,
+    now: i64,
+) -> Result<()> {
+    if envelope.grantee != Pubkey::default() {
+        recipient.release_envelope(envelope.recipient_key_id);
+    }
+    recipient.add_envelope(key.key_id)?;
+
+    let grantee = recipient.owner;
+    envelope.patient = patient.key();
+    envelope.grantee = grantee;
+    envelope.recipient_key_id = key.key_id;
+    envelope.recipient_key = key.public_key;
+    envelope.epoch = patient.data_key_epoch;
+    envelope.wrapped_key = wrapped_key;
+    envelope.created_at = now;
+    envelope.bump = envelope_bump;
+
+    msg!("Data key envelope issued to {} (key #{})", grantee, key.key_id);
+
+    emit!(KeyEnvelopeIssuedEvent {
+        patient: patient.key(),
+        grantee,
+        envelope: envelope.key(),
+        recipient_key_id: key.key_id,
+        recipient_key: key.public_key,
+        epoch: envelope.epoch,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Resolve the grantee's encryption key referenced while granting access and,
+/// if the patient supplied a wrapped key, issue the envelope
+///
+/// Returns the id of the key in use (None when no key was referenced). A
+/// wrapped key requires the envelope account and the grantee's registration.
+pub(crate) fn share_data_key(
+    envelope: Option<&mut Account<KeyEnvelope>>,
+    envelope_bump: Option<u8>,
+    recipient: Option<&mut Account<EncryptionKey>>,
+    patient: &Account<Patient>,
+    wrapped_key: Option<WrappedKey>,
+    encryption_key_id: Option<u32>,
+    now: i64,
+) -> Result<Option<u32>> {
+    if wrapped_key.is_none() && encryption_key_id.is_none() {
+        require!(envelope.is_none(), CypherMedError::WrappedKeyRequired);
+        return Ok(None);
+    }
+    let recipient = recipient.ok_or(CypherMedError::EncryptionKeyNotRegistered)?;
+    let key = *recipient.usable_key(encryption_key_id)?;
+
+    match wrapped_key {
+        Some(wrapped_key) => {
+            let envelope = envelope.ok_or(CypherMedError::KeyEnvelopeRequired)?;
+            seal_key_envelope(
+                envelope,
+                envelope_bump.unwrap_or_default(),
+                recipient,
+                &key,
+                patient,
+                wrapped_key,
+                now,
+            )?;
+        }
+        None => require!(envelope.is_none(), CypherMedError::WrappedKeyRequired),
+    }
+    Ok(Some(key.key_id))
+}
+
+/// Close the grant's key envelope, if one was issued, refunding `destination`
+///
+/// `recipient` is the grantee's key registration, which stops counting the
+/// envelope; it is required whenever an envelope exists.
+pub(crate) fn close_key_envelope<'info>(
+    envelope: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    recipient: Option<&mut EncryptionKey>,
+) -> Result<bool> {
+    if envelope.data_is_empty() {
+        return Ok(false);
+    }
+    require_keys_eq!(*envelope.owner, crate::ID, CypherMedError::Unauthorized);
+
+    let recipient_key_id = {
+        let data = envelope.try_borrow_data()?;
+        KeyEnvelope::try_deserialize(&mut &data[..])?.recipient_key_id
+    };
+    recipient
+        .ok_or(CypherMedError::EncryptionKeyNotRegistered)?
+        .release_envelope(recipient_key_id);
+
+    // Same steps as Anchor's `close` constraint
+    let refund = envelope.lamports();
+    **destination.try_borrow_mut_lamports()? = destination.lamports().checked_add(refund).unwrap();
+    **envelope.try_borrow_mut_lamports()? = 0;
+    envelope.assign(&System::id());
+    envelope.resize(0)?;
+    Ok(true)
+}
+
+#[derive(Accounts)]
+pub struct RegisterEncryptionKey<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = EncryptionKey::LEN,
+        seeds = [b"encryption_key", owner.key().as_ref()],
+        bump
+    )]
+    pub encryption_key: Account<'info, EncryptionKey>,
+
+    /// CHECK: Instructions sysvar, read to find the owner's binding signature
+    #[account(address = solana_sdk_ids::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeEncryptionKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"encryption_key", owner.key().as_ref()],
+        bump = encryption_key.bump,
+        has_one = owner @ CypherMedError::Unauthorized
+    )]
+    pub encryption_key: Account<'info, EncryptionKey>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct IssueKeyEnvelope<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        mut,
+        seeds = [b"access_grant", patient.key().as_ref(), access_grant.provider.as_ref()],
+        bump = access_grant.bump,
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+
+    #[account(
+        mut,
+        seeds = [b"encryption_key", access_grant.provider.as_ref()],
+        bump = grantee_encryption_key.bump,
+    )]
+    pub grantee_encryption_key: Account<'info, EncryptionKey>,
+
+    /// Re-issuing overwrites the previous envelope (e.g. after a key rotation)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = KeyEnvelope::LEN,
+        seeds = [b"key_envelope", patient.key().as_ref(), access_grant.provider.as_ref()],
+        bump
+    )]
+    pub key_envelope: Account<'info, KeyEnvelope>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct EncryptionKeyRegisteredEvent {
+    pub owner: Pubkey,
+    pub key_id: u32,
+    pub x25519_public_key: [u8; 32],
+    pub rotated_key_id: Option<u32>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EncryptionKeyRevokedEvent {
+    pub owner: Pubkey,
+    pub key_id: u32,
+    pub envelopes_closed: u32,
+    pub envelopes_outstanding: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeyEnvelopeIssuedEvent {
+    pub patient: Pubkey,
+    pub grantee: Pubkey,
+    pub envelope: Pubkey,
+    pub recipient_key_id: u32,
+    pub recipient_key: [u8; 32],
+    pub epoch: u32,
+    pub timestamp: i64,
+}
@@ -0,0 +1,310 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::CypherMedError;
+use crate::audit::*;
+
+/// Escrow the patient's emergency decryption key with custodians (patient only)
+///
+/// The key is split into Shamir shares client-side; each share arrives
+/// wrapped to its custodian's X25519 key along with a commitment to it.
+/// Reconfiguring replaces every share and closes any open release window.
+pub fn configure_key_escrow(
+    ctx: Context<ConfigureKeyEscrow>,
+    threshold: u8,
+    shares: Vec<EscrowShareInput>,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let escrow = &mut ctx.accounts.key_escrow;
+    let clock = Clock::get()?;
+
+    require!(patient.is_active, CypherMedError::PatientInactive);
+    require!(
+        shares.len() <= KeyEscrow::MAX_CUSTODIANS,
+        CypherMedError::TooManyCustodians
+    );
+    require!(
+        threshold >= 2 && threshold as usize <= shares.len(),
+        CypherMedError::InvalidEscrowThreshold
+    );
+    for (i, share) in shares.iter().enumerate() {
+        require!(share.share_index != 0, CypherMedError::InvalidEscrowShare);
+        require!(
+            shares[..i].iter().all(|other| other.custodian != share.custodian
+                && other.share_index != share.share_index),
+            CypherMedError::InvalidEscrowShare
+        );
+    }
+
+    let custodian_count = shares.len();
+    escrow.set_inner(KeyEscrow {
+        patient: patient.key(),
+        threshold,
+        key_epoch: patient.data_key_epoch,
+        custodians: shares
+            .into_iter()
+            .map(|share| EscrowCustodian {
+                custodian: share.custodian,
+                recipient_key: share.recipient_key,
+                share_index: share.share_index,
+                commitment: share.commitment,
+                wrapped_share: share.wrapped_share,
+                released: None,
+            })
+            .collect(),
+        release_to: None,
+        release_opened_at: None,
+        configured_at: clock.unix_timestamp,
+        bump: ctx.bumps.key_escrow,
+    });
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::ConfigureKeyEscrow,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Key escrowed {} of {}", threshold, custodian_count)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!(
+        "Key escrow configured for patient {}: {} of {} custodians",
+        patient.key(),
+        threshold,
+        custodian_count
+    );
+
+    emit!(KeyEscrowConfiguredEvent {
+        patient: patient.key(),
+        escrow: escrow.key(),
+        threshold,
+        custodians: escrow.custodians.iter().map(|entry| entry.custodian).collect(),
+        key_epoch: escrow.key_epoch,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Release a custodian's share to the emergency responder (custodian only)
+///
+/// Only possible while the window opened by `emergency_access` lasts. The
+/// custodian unwraps their share off-chain and re-wraps it to the
+/// responder's registered encryption key.
+pub fn release_escrow_share(
+    ctx: Context<ReleaseEscrowShare>,
+    wrapped_share: WrappedKey,
+    encryption_key_id: Option<u32>,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let escrow = &mut ctx.accounts.key_escrow;
+    let custodian = ctx.accounts.custodian.key();
+    let clock = Clock::get()?;
+
+    let responder = escrow
+        .release_recipient(clock.unix_timestamp)
+        .ok_or(CypherMedError::EscrowReleaseNotOpen)?;
+    let recipient = &ctx.accounts.responder_encryption_key;
+    require_keys_eq!(recipient.owner, responder, CypherMedError::EscrowReleaseNotOpen);
+    let key = *recipient.usable_key(encryption_key_id)?;
+
+    let entry = escrow.custodian_mut(&custodian)?;
+    require!(entry.released.is_none(), CypherMedError::EscrowShareAlreadyReleased);
+    entry.released = Some(ReleasedShare {
+        recipient_key_id: key.key_id,
+        wrapped_share,
+        released_at: clock.unix_timestamp,
+    });
+    let share_index = entry.share_index;
+    let released = escrow.released_count() as u8;
+
+    // Custodians who are also grantees (e.g. a hospital HSM) act under their grant's role
+    let custodian_role = ctx
+        .accounts
+        .access_grant
+        .as_ref()
+        .filter(|grant| grant.is_active)
+        .map_or(Role::EscrowCustodian, |grant| grant.role);
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: custodian,
+        accessor_role: custodian_role,
+        action: AccessAction::ReleaseEscrowShare,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: true,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!(
+            "Escrow share #{} released to {} ({} of {})",
+            share_index, responder, released, escrow.threshold
+        )),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!(
+        "⚠️ ESCROW RELEASE: Share #{} of patient {} released to {}",
+        share_index,
+        patient.key(),
+        responder
+    );
+
+    emit!(EscrowShareReleasedEvent {
+        patient: patient.key(),
+        custodian,
+        responder,
+        share_index,
+        released,
+        threshold: escrow.threshold,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ConfigureKeyEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = KeyEscrow::LEN,
+        seeds = [b"key_escrow", patient.key().as_ref()],
+        bump
+    )]
+    pub key_escrow: Account<'info, KeyEscrow>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseEscrowShare<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", patient.authority.as_ref()],
+        bump = patient.bump,
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        mut,
+        seeds = [b"key_escrow", patient.key().as_ref()],
+        bump = key_escrow.bump,
+    )]
+    pub key_escrow: Account<'info, KeyEscrow>,
+
+    /// Encryption key of the responder the release window is open for
+    #[account(
+        seeds = [b"encryption_key", responder_encryption_key.owner.as_ref()],
+        bump = responder_encryption_key.bump,
+    )]
+    pub responder_encryption_key: Account<'info, EncryptionKey>,
+
+    /// Custodian's own access grant, if they have one
+    #[account(
+        seeds = [b"access_grant", patient.key().as_ref(), custodian.key().as_ref()],
+        bump = access_grant.bump,
+    )]
+    pub access_grant: Option<Account<'info, AccessGrant>>,
+
+    #[account(
+        init,
+        payer = custodian,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub custodian: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct KeyEscrowConfiguredEvent {
+    pub patient: Pubkey,
+    pub escrow: Pubkey,
+    pub threshold: u8,
+    pub custodians: Vec<Pubkey>,
+    pub key_epoch: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowShareReleasedEvent {
+    pub patient: Pubkey,
+    pub custodian: Pubkey,
+    pub responder: Pubkey,
+    pub share_index: u8,
+    pub released: u8,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowReleaseOpenedEvent {
+    pub patient: Pubkey,
+    pub responder: Pubkey,
+    pub closes_at: i64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,342 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::CypherMedError;
+use crate::audit::*;
+
+/// Start rotating the patient's data key (patient only)
+///
+/// Bumps the patient's data-key epoch. The patient then marks the records
+/// encrypted under the old key with `mark_records_for_rekey`, re-encrypts
+/// them and calls `update_record`, and re-issues envelopes to the grantees
+/// that remain with `issue_key_envelope`.
+pub fn start_key_rotation(
+    ctx: Context<StartKeyRotation>,
+    revoked_grantee: Option<Pubkey>,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let rotation = &mut ctx.accounts.key_rotation;
+    let clock = Clock::get()?;
+
+    require!(patient.is_active, CypherMedError::PatientInactive);
+    require!(!rotation.in_progress(), CypherMedError::KeyRotationInProgress);
+
+    let compromised_epoch = patient.data_key_epoch;
+    patient.data_key_epoch = compromised_epoch.checked_add(1).unwrap();
+    patient.updated_at = clock.unix_timestamp;
+
+    rotation.set_inner(KeyRotation {
+        patient: patient.key(),
+        compromised_epoch,
+        new_epoch: patient.data_key_epoch,
+        revoked_grantee,
+        started_at: clock.unix_timestamp,
+        completed_at: None,
+        records_marked: 0,
+        records_rekeyed: 0,
+        records_dropped: 0,
+        pending_records: Vec::new(),
+        bump: ctx.bumps.key_rotation,
+    });
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::StartKeyRotation,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!(
+            "Data key rotated from epoch {} to {}",
+            compromised_epoch, patient.data_key_epoch
+        )),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!(
+        "Key rotation started for patient {} (epoch {} -> {})",
+        patient.key(),
+        compromised_epoch,
+        patient.data_key_epoch
+    );
+
+    emit!(KeyRotationStartedEvent {
+        patient: patient.key(),
+        compromised_epoch,
+        new_epoch: patient.data_key_epoch,
+        revoked_grantee,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Flag records still encrypted under an old data key for re-encryption
+///
+/// The records are passed, writable, in `remaining_accounts`.
+pub fn mark_records_for_rekey<'info>(
+    ctx: Context<'_, '_, 'info, 'info, MarkRecordsForRekey<'info>>,
+) -> Result<()> {
+    let patient = &ctx.accounts.patient;
+    let rotation = &mut ctx.accounts.key_rotation;
+    let clock = Clock::get()?;
+
+    require!(rotation.in_progress(), CypherMedError::NoKeyRotationInProgress);
+
+    for info in ctx.remaining_accounts.iter() {
+        let mut record: Account<'info, MedicalRecord> = Account::try_from(info)?;
+        let (expected, _) = Pubkey::find_program_address(
+            &[b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+            ctx.program_id,
+        );
+        require_keys_eq!(info.key(), expected, CypherMedError::Unauthorized);
+        // A deleted record can never be re-keyed, so it would block completion
+        require!(record.is_active, CypherMedError::RecordInactive);
+        require!(
+            record.key_epoch < patient.data_key_epoch,
+            CypherMedError::RecordAlreadyRekeyed
+        );
+        require!(
+            !rotation.pending_records.contains(info.key),
+            CypherMedError::RecordAlreadyPendingRekey
+        );
+
+        record.needs_rekey = true;
+        rotation.mark_pending(info.key())?;
+        record.exit(ctx.program_id)?;
+    }
+
+    msg!(
+        "{} records awaiting re-key for patient {}",
+        rotation.pending_records.len(),
+        patient.key()
+    );
+
+    emit!(RecordsMarkedForRekeyEvent {
+        patient: patient.key(),
+        marked: ctx.remaining_accounts.len() as u32,
+        pending: rotation.pending_records.len() as u32,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Finish the rotation once every marked record is re-keyed or deleted (patient only)
+pub fn complete_key_rotation(ctx: Context<FinishKeyRotation>) -> Result<()> {
+    let rotation = &mut ctx.accounts.key_rotation;
+    let clock = Clock::get()?;
+
+    require!(rotation.in_progress(), CypherMedError::NoKeyRotationInProgress);
+    require!(rotation.pending_records.is_empty(), CypherMedError::RekeysPending);
+
+    rotation.completed_at = Some(clock.unix_timestamp);
+
+    msg!("Key rotation to epoch {} complete", rotation.new_epoch);
+
+    emit!(KeyRotationCompletedEvent {
+        patient: rotation.patient,
+        new_epoch: rotation.new_epoch,
+        records_rekeyed: rotation.records_rekeyed,
+        abandoned: 0,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Abandon the rotation, leaving any still-pending records on their old
+/// epoch (patient only)
+///
+/// The data-key epoch stays bumped; a later rotation can mark the abandoned
+/// records again.
+pub fn cancel_key_rotation(ctx: Context<FinishKeyRotation>) -> Result<()> {
+    let rotation = &mut ctx.accounts.key_rotation;
+    let clock = Clock::get()?;
+
+    require!(rotation.in_progress(), CypherMedError::NoKeyRotationInProgress);
+
+    let abandoned = rotation.pending_records.len() as u32;
+    rotation.pending_records.clear();
+    rotation.completed_at = Some(clock.unix_timestamp);
+
+    msg!(
+        "Key rotation to epoch {} cancelled with {} records pending",
+        rotation.new_epoch,
+        abandoned
+    );
+
+    emit!(KeyRotationCompletedEvent {
+        patient: rotation.patient,
+        new_epoch: rotation.new_epoch,
+        records_rekeyed: rotation.records_rekeyed,
+        abandoned,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Clear a marked record's re-key flag once its content has been
+/// re-encrypted, updating the rotation status
+pub(crate) fn complete_rekey(
+    record: &mut Account<MedicalRecord>,
+    rotation: Option<&mut Account<KeyRotation>>,
+) -> Result<()> {
+    let rotation = rotation.ok_or(CypherMedError::KeyRotationRequired)?;
+    record.needs_rekey = false;
+    rotation.mark_rekeyed(&record.key());
+    Ok(())
+}
+
+/// Take a record that is being deleted or purged off the rotation's pending
+/// list, so the rotation can still be completed
+pub(crate) fn drop_rekey(
+    record: &mut Account<MedicalRecord>,
+    rotation: Option<&mut Account<KeyRotation>>,
+) -> Result<()> {
+    if !record.needs_rekey {
+        return Ok(());
+    }
+    let rotation = rotation.ok_or(CypherMedError::KeyRotationRequired)?;
+    record.needs_rekey = false;
+    rotation.drop_pending(&record.key());
+    Ok(())
+}
+
+/// Put a restored record that is still on an old data key back on the
+/// pending list of the rotation in progress
+pub(crate) fn resume_rekey(
+    record: &mut Account<MedicalRecord>,
+    data_key_epoch: u32,
+    rotation: Option<&mut Account<KeyRotation>>,
+) -> Result<()> {
+    if record.key_epoch >= data_key_epoch {
+        return Ok(());
+    }
+    // Any epoch past the first was set by a rotation, so its account exists
+    let rotation = rotation.ok_or(CypherMedError::KeyRotationRequired)?;
+    if rotation.in_progress() && !rotation.pending_records.contains(&record.key()) {
+        record.needs_rekey = true;
+        rotation.mark_pending(record.key())?;
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct StartKeyRotation<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = KeyRotation::LEN,
+        seeds = [b"key_rotation", patient.key().as_ref()],
+        bump
+    )]
+    pub key_rotation: Account<'info, KeyRotation>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MarkRecordsForRekey<'info> {
+    #[account(
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        mut,
+        seeds = [b"key_rotation", patient.key().as_ref()],
+        bump = key_rotation.bump,
+    )]
+    pub key_rotation: Account<'info, KeyRotation>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinishKeyRotation<'info> {
+    #[account(
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        mut,
+        seeds = [b"key_rotation", patient.key().as_ref()],
+        bump = key_rotation.bump,
+    )]
+    pub key_rotation: Account<'info, KeyRotation>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct KeyRotationStartedEvent {
+    pub patient: Pubkey,
+    pub compromised_epoch: u32,
+    pub new_epoch: u32,
+    pub revoked_grantee: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecordsMarkedForRekeyEvent {
+    pub patient: Pubkey,
+    pub marked: u32,
+    pub pending: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeyRotationCompletedEvent {
+    pub patient: Pubkey,
+    pub new_epoch: u32,
+    pub records_rekeyed: u32,
+    /// Records still pending when the rotation was cancelled
+    pub abandoned: u32,
+    pub timestamp: i64,
+}
@@ -0,0 +1,218 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::CypherMedError;
+
+/// Place a legal hold on a record (configured legal authority only)
+pub fn place_legal_hold(
+    ctx: Context<PlaceLegalHold>,
+    reason: String,
+) -> Result<()> {
+    require!(!reason.is_empty(), CypherMedError::LegalHoldReasonRequired);
+    require!(reason.len() <= 200, CypherMedError::LegalHoldReasonTooLong);
+
+    let hold = &mut ctx.accounts.legal_hold;
+    let clock = Clock::get()?;
+
+    hold.record = ctx.accounts.record.key();
+    hold.patient = ctx.accounts.patient.key();
+    hold.placed_by = ctx.accounts.legal_authority.key();
+    hold.reason = reason.clone();
+    hold.placed_at = clock.unix_timestamp;
+    hold.bump = ctx.bumps.legal_hold;
+
+    msg!("Legal hold placed on record {}", hold.record);
+
+    emit!(LegalHoldPlacedEvent {
+        record: hold.record,
+        patient: hold.patient,
+        placed_by: hold.placed_by,
+        reason,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Lift a legal hold (configured legal authority only)
+pub fn lift_legal_hold(ctx: Context<LiftLegalHold>) -> Result<()> {
+    let hold = &ctx.accounts.legal_hold;
+    let clock = Clock::get()?;
+
+    msg!("Legal hold lifted from record {}", hold.record);
+
+    emit!(LegalHoldLiftedEvent {
+        record: hold.record,
+        patient: hold.patient,
+        lifted_by: ctx.accounts.legal_authority.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Crank: flag a record that has outlived its maximum retention period
+pub fn flag_retention_expired(ctx: Context<FlagRetentionExpired>) -> Result<()> {
+    let record = &mut ctx.accounts.record;
+    let clock = Clock::get()?;
+
+    require!(
+        record.retention_flagged_at.is_none(),
+        CypherMedError::RetentionAlreadyFlagged
+    );
+    let definition = RecordTypeDefinition::load(&ctx.accounts.record_type_definition)?;
+    let max_retention = ctx
+        .accounts
+        .config
+        .effective_retention(record.record_type, definition.as_ref())
+        .and_then(|policy| policy.max_retention_seconds)
+        .ok_or(CypherMedError::RetentionNotExpired)?;
+    require!(
+        clock.unix_timestamp > record.created_at.saturating_add(max_retention),
+        CypherMedError::RetentionNotExpired
+    );
+
+    record.retention_flagged_at = Some(clock.unix_timestamp);
+
+    msg!("Record {} is past its maximum retention", record.key());
+
+    emit!(RetentionExpiredEvent {
+        record: record.key(),
+        patient: record.patient,
+        record_type: record.record_type,
+        created_at: record.created_at,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Fail unless `record` may be deleted or purged: it must not be under a
+/// legal hold and must have reached its minimum retention period
+pub(crate) fn require_deletable(
+    config: &ProgramConfig,
+    record: &MedicalRecord,
+    legal_hold: &AccountInfo,
+    record_type_definition: &AccountInfo,
+    now: i64,
+) -> Result<()> {
+    require!(legal_hold.data_is_empty(), CypherMedError::RecordUnderLegalHold);
+
+    let definition = RecordTypeDefinition::load(record_type_definition)?;
+    if let Some(min_retention) = config
+        .effective_retention(record.record_type, definition.as_ref())
+        .and_then(|policy| policy.min_retention_seconds)
+    {
+        require!(
+            now >= record.created_at.saturating_add(min_retention),
+            CypherMedError::MinimumRetentionNotMet
+        );
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PlaceLegalHold<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.legal_authority.is_some() @ CypherMedError::LegalAuthorityNotConfigured,
+        constraint = config.legal_authority == Some(legal_authority.key()) @ CypherMedError::Unauthorized,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"patient", patient.authority.as_ref()],
+        bump = patient.bump,
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    #[account(
+        init,
+        payer = legal_authority,
+        space = LegalHold::LEN,
+        seeds = [b"legal_hold", record.key().as_ref()],
+        bump
+    )]
+    pub legal_hold: Account<'info, LegalHold>,
+
+    #[account(mut)]
+    pub legal_authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LiftLegalHold<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.legal_authority == Some(legal_authority.key()) @ CypherMedError::Unauthorized,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"legal_hold", legal_hold.record.as_ref()],
+        bump = legal_hold.bump,
+        close = legal_authority,
+    )]
+    pub legal_hold: Account<'info, LegalHold>,
+
+    #[account(mut)]
+    pub legal_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FlagRetentionExpired<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"record", record.patient.as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    /// CHECK: Definition PDA for the record's type; empty for a built-in type without one
+    #[account(
+        seeds = [b"record_type".as_ref(), &record.record_type.to_le_bytes()],
+        bump,
+    )]
+    pub record_type_definition: UncheckedAccount<'info>,
+}
+
+#[event]
+pub struct LegalHoldPlacedEvent {
+    pub record: Pubkey,
+    pub patient: Pubkey,
+    pub placed_by: Pubkey,
+    pub reason: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LegalHoldLiftedEvent {
+    pub record: Pubkey,
+    pub patient: Pubkey,
+    pub lifted_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RetentionExpiredEvent {
+    pub record: Pubkey,
+    pub patient: Pubkey,
+    pub record_type: RecordTypeCode,
+    pub created_at: i64,
+    pub timestamp: i64,
+}
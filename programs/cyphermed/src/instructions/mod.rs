@@ -1,23 +1,51 @@
 pub mod initialize_patient;
 pub mod create_record;
+pub mod signing_key;
 pub mod grant_access;
 pub mod revoke_access;
+pub mod key_envelope;
+pub mod key_rotation;
+pub mod key_escrow;
 pub mod access_record;
+pub mod check_access;
+pub mod verify_integrity;
 pub mod emergency_access;
 pub mod request_access;
 pub mod approve_deny_request;
 pub mod update_delete_record;
+pub mod attachment;
+pub mod pending_deletion;
+pub mod purge_record;
+pub mod legal_hold;
+pub mod amendment;
 pub mod batch_grant;
 pub mod patient_management;
+pub mod program_config;
+pub mod record_type_registry;
+pub mod audit_settings;
 
 pub use initialize_patient::*;
 pub use create_record::*;
+pub use signing_key::*;
 pub use grant_access::*;
 pub use revoke_access::*;
+pub use key_envelope::*;
+pub use key_rotation::*;
+pub use key_escrow::*;
 pub use access_record::*;
+pub use check_access::*;
+pub use verify_integrity::*;
 pub use emergency_access::*;
 pub use request_access::*;
 pub use approve_deny_request::*;
 pub use update_delete_record::*;
+pub use attachment::*;
+pub use pending_deletion::*;
+pub use purge_record::*;
+pub use legal_hold::*;
+pub use amendment::*;
 pub use batch_grant::*;
 pub use patient_management::*;
+pub use program_config::*;
+pub use record_type_registry::*;
+pub use audit_settings::*;
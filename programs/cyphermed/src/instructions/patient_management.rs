@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::CypherMedError;
+use crate::audit::*;
 
 /// Update patient account information
 pub fn update_patient(
@@ -17,6 +18,32 @@ pub fn update_patient(
     patient.emergency_contact = emergency_contact;
     patient.updated_at = clock.unix_timestamp;
 
+    // Log the change in the audit trail
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::UpdatePatient,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some("Emergency contact updated".to_string()),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
     msg!(
         "Patient {} updated emergency contact",
         patient.key()
@@ -25,6 +52,58 @@ pub fn update_patient(
     Ok(())
 }
 
+/// Require (or stop requiring) the patient's co-signature on provider deletions
+pub fn set_deletion_policy(
+    ctx: Context<SetDeletionPolicy>,
+    cosign_window: Option<i64>,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let clock = Clock::get()?;
+
+    // Verify patient is active
+    require!(patient.is_active, CypherMedError::PatientInactive);
+    if let Some(window) = cosign_window {
+        require!(window > 0, CypherMedError::InvalidDeletionCoSignWindow);
+    }
+
+    patient.deletion_cosign_window = cosign_window;
+    patient.updated_at = clock.unix_timestamp;
+
+    // Log the change in the audit trail
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::UpdatePatient,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Deletion co-sign window set to {:?}", cosign_window)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!(
+        "Patient {} deletion co-sign window: {:?}",
+        patient.key(),
+        cosign_window
+    );
+    
+    Ok(())
+}
+
 /// Deactivate patient account (soft delete)
 pub fn deactivate_patient(
     ctx: Context<DeactivatePatient>,
@@ -39,6 +118,32 @@ pub fn deactivate_patient(
     patient.is_active = false;
     patient.updated_at = clock.unix_timestamp;
 
+    // Log the deactivation in the audit trail
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::DeactivatePatient,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some("Patient account deactivated".to_string()),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
     msg!(
         "Patient {} account deactivated",
         patient.key()
@@ -67,6 +172,32 @@ pub fn reactivate_patient(
     patient.is_active = true;
     patient.updated_at = clock.unix_timestamp;
 
+    // Log the reactivation in the audit trail
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::ReactivatePatient,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some("Patient account reactivated".to_string()),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
     msg!(
         "Patient {} account reactivated",
         patient.key()
@@ -91,7 +222,56 @@ pub struct UpdatePatient<'info> {
     )]
     pub patient: Account<'info, Patient>,
 
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
     pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetDeletionPolicy<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -104,7 +284,25 @@ pub struct DeactivatePatient<'info> {
     )]
     pub patient: Account<'info, Patient>,
 
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
     pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -117,7 +315,25 @@ pub struct ReactivatePatient<'info> {
     )]
     pub patient: Account<'info, Patient>,
 
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
     pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[event]
@@ -0,0 +1,317 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::CypherMedError;
+use crate::audit::*;
+use crate::authorization::*;
+use crate::instructions::RecordDeletedEvent;
+use crate::instructions::legal_hold::require_deletable;
+use crate::instructions::key_rotation::drop_rekey;
+
+/// Apply a pending deletion, either co-signed by the patient or after the
+/// co-sign window has elapsed
+///
+/// If the record was already deleted some other way, the request is
+/// discarded instead so the requester still gets their rent back.
+pub fn confirm_deletion(ctx: Context<ConfirmDeletion>) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let record = &mut ctx.accounts.record;
+    let pending = &ctx.accounts.pending_deletion;
+    let clock = Clock::get()?;
+
+    if !record.is_active {
+        let entry = AuditLog {
+            patient: patient.key(),
+            sequence: 0,
+            prev_hash: [0; 32],
+            record: Some(record.key()),
+            accessor: pending.requested_by,
+            accessor_role: pending.requester_role,
+            action: AccessAction::CancelDeletion,
+            record_type: Some(record.record_type),
+            timestamp: clock.unix_timestamp,
+            success: true,
+            decision: AccessDecision::Allowed,
+            is_emergency: false,
+            emergency_justification: None,
+            client_info: None,
+            metadata: Some("Pending deletion discarded, record already inactive".to_string()),
+            bump: ctx.bumps.audit_log.unwrap_or_default(),
+        };
+        write_audit_entry(
+            patient,
+            ctx.accounts.audit_log.as_mut(),
+            &ctx.accounts.compressed_audit,
+            entry,
+        )?;
+
+        msg!("Record {} already inactive; pending deletion discarded", record.key());
+
+        emit!(DeletionCancelledEvent {
+            record: record.key(),
+            patient: patient.key(),
+            requested_by: pending.requested_by,
+            timestamp: clock.unix_timestamp,
+        });
+
+        // `close = requested_by` refunds the pending account's rent
+        return Ok(());
+    }
+
+    require_deletable(
+        &ctx.accounts.config,
+        record,
+        &ctx.accounts.legal_hold,
+        &ctx.accounts.record_type_definition,
+        clock.unix_timestamp,
+    )?;
+
+    let cosigned = ctx.accounts.executor.key() == patient.authority;
+    if !cosigned {
+        require!(
+            clock.unix_timestamp >= pending.executes_at,
+            CypherMedError::DeletionCoSignPending
+        );
+
+        // Without the patient's co-signature the requester must still be authorized
+        authorize(
+            patient,
+            &pending.requested_by,
+            ctx.accounts.requester_grant.as_deref(),
+            RecordOperation::Delete,
+            RecordTarget::Existing(record),
+            clock.unix_timestamp,
+        )
+        .decision
+        .require_allowed()?;
+    }
+
+    // Soft delete - mark as inactive
+    drop_rekey(record, ctx.accounts.key_rotation.as_mut())?;
+    record.soft_delete(pending.requested_by, clock.unix_timestamp);
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: pending.requested_by,
+        accessor_role: pending.requester_role,
+        action: AccessAction::Delete,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!(
+            "Soft deleted ({})",
+            if cosigned { "co-signed by patient" } else { "co-sign window elapsed" }
+        )),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!(
+        "Record {} soft-deleted by {} (co-signed: {})",
+        record.key(),
+        pending.requested_by,
+        cosigned
+    );
+
+    emit!(RecordDeletedEvent {
+        record: record.key(),
+        patient: patient.key(),
+        deleter: pending.requested_by,
+        reason: pending.reason.clone(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Reject a pending deletion (patient only)
+pub fn cancel_deletion(ctx: Context<CancelDeletion>) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let pending = &ctx.accounts.pending_deletion;
+    let clock = Clock::get()?;
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(pending.record),
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::CancelDeletion,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Deletion requested by {} cancelled", pending.requested_by)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!("Pending deletion of record {} cancelled", pending.record);
+
+    emit!(DeletionCancelledEvent {
+        record: pending.record,
+        patient: patient.key(),
+        requested_by: pending.requested_by,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ConfirmDeletion<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", patient.authority.as_ref()],
+        bump = patient.bump,
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        mut,
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// CHECK: Legal hold PDA for the record; must be empty (no hold in place)
+    #[account(
+        seeds = [b"legal_hold", record.key().as_ref()],
+        bump,
+    )]
+    pub legal_hold: UncheckedAccount<'info>,
+
+    /// CHECK: Definition PDA for the record's type; empty for a built-in type without one
+    #[account(
+        seeds = [b"record_type".as_ref(), &record.record_type.to_le_bytes()],
+        bump,
+    )]
+    pub record_type_definition: UncheckedAccount<'info>,
+
+    /// Patient's key rotation (required when the record is marked for re-key)
+    #[account(
+        mut,
+        seeds = [b"key_rotation", patient.key().as_ref()],
+        bump = key_rotation.bump,
+    )]
+    pub key_rotation: Option<Account<'info, KeyRotation>>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_deletion", record.key().as_ref()],
+        bump = pending_deletion.bump,
+        has_one = requested_by,
+        close = requested_by,
+    )]
+    pub pending_deletion: Account<'info, PendingDeletion>,
+
+    /// Requester's access grant (needed when the window elapsed without co-signature)
+    #[account(
+        seeds = [b"access_grant", patient.key().as_ref(), pending_deletion.requested_by.as_ref()],
+        bump,
+    )]
+    pub requester_grant: Option<Account<'info, AccessGrant>>,
+
+    /// CHECK: Refunded the pending deletion's rent; checked by `has_one`
+    #[account(mut)]
+    pub requested_by: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = executor,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelDeletion<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_deletion", pending_deletion.record.as_ref()],
+        bump = pending_deletion.bump,
+        constraint = pending_deletion.patient == patient.key() @ CypherMedError::Unauthorized,
+        has_one = requested_by,
+        close = requested_by,
+    )]
+    pub pending_deletion: Account<'info, PendingDeletion>,
+
+    /// CHECK: Refunded the pending deletion's rent; checked by `has_one`
+    #[account(mut)]
+    pub requested_by: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct DeletionCancelledEvent {
+    pub record: Pubkey,
+    pub patient: Pubkey,
+    pub requested_by: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,257 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::CypherMedError;
+
+/// Initialize the global program configuration
+///
+/// Restricted to the program's upgrade authority, so nobody can front-run
+/// the deployer and take over the config.
+pub fn initialize_config(
+    ctx: Context<InitializeConfig>,
+    emergency_window_seconds: i64,
+    emergency_soft_threshold: u32,
+    emergency_hard_cap: u32,
+) -> Result<()> {
+    validate_emergency_limits(
+        emergency_window_seconds,
+        emergency_soft_threshold,
+        emergency_hard_cap,
+    )?;
+
+    let config = &mut ctx.accounts.config;
+    let clock = Clock::get()?;
+
+    config.authority = ctx.accounts.authority.key();
+    config.emergency_window_seconds = emergency_window_seconds;
+    config.emergency_soft_threshold = emergency_soft_threshold;
+    config.emergency_hard_cap = emergency_hard_cap;
+    config.legal_authority = None;
+    config.retention_policies = Vec::new();
+    config.storage_schemes = Vec::new();
+    config.updated_at = clock.unix_timestamp;
+    config.bump = ctx.bumps.config;
+
+    msg!("Program config initialized by: {}", config.authority);
+    
+    Ok(())
+}
+
+/// Update emergency access limits in the program configuration
+pub fn update_config(
+    ctx: Context<UpdateConfig>,
+    emergency_window_seconds: i64,
+    emergency_soft_threshold: u32,
+    emergency_hard_cap: u32,
+) -> Result<()> {
+    validate_emergency_limits(
+        emergency_window_seconds,
+        emergency_soft_threshold,
+        emergency_hard_cap,
+    )?;
+
+    let config = &mut ctx.accounts.config;
+    let clock = Clock::get()?;
+
+    config.emergency_window_seconds = emergency_window_seconds;
+    config.emergency_soft_threshold = emergency_soft_threshold;
+    config.emergency_hard_cap = emergency_hard_cap;
+    config.updated_at = clock.unix_timestamp;
+
+    msg!(
+        "Emergency limits updated: {} soft / {} hard per {}s",
+        emergency_soft_threshold,
+        emergency_hard_cap,
+        emergency_window_seconds
+    );
+    
+    Ok(())
+}
+
+/// Set the wallet allowed to place and lift legal holds
+pub fn set_legal_authority(
+    ctx: Context<UpdateConfig>,
+    legal_authority: Option<Pubkey>,
+) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let clock = Clock::get()?;
+
+    config.legal_authority = legal_authority;
+    config.updated_at = clock.unix_timestamp;
+
+    msg!("Legal authority set to {:?}", legal_authority);
+    
+    Ok(())
+}
+
+/// Set (or clear, when both periods are None) the retention rules for a record type
+pub fn set_retention_policy(
+    ctx: Context<UpdateConfig>,
+    record_type: RecordTypeCode,
+    min_retention_seconds: Option<i64>,
+    max_retention_seconds: Option<i64>,
+) -> Result<()> {
+    let positive = |period: Option<i64>| period.is_none_or(|seconds| seconds > 0);
+    require!(
+        positive(min_retention_seconds) && positive(max_retention_seconds),
+        CypherMedError::InvalidRetentionPolicy
+    );
+    if let (Some(min), Some(max)) = (min_retention_seconds, max_retention_seconds) {
+        require!(min <= max, CypherMedError::InvalidRetentionPolicy);
+    }
+
+    let config = &mut ctx.accounts.config;
+    let clock = Clock::get()?;
+
+    config.retention_policies.retain(|policy| policy.record_type != record_type);
+    if min_retention_seconds.is_some() || max_retention_seconds.is_some() {
+        require!(
+            config.retention_policies.len() < ProgramConfig::MAX_RETENTION_POLICIES,
+            CypherMedError::RetentionPolicyLimitReached
+        );
+        config.retention_policies.push(RetentionPolicy {
+            record_type,
+            min_retention_seconds,
+            max_retention_seconds,
+        });
+    }
+    config.updated_at = clock.unix_timestamp;
+
+    msg!(
+        "Retention for {:?}: min {:?}s, max {:?}s",
+        record_type,
+        min_retention_seconds,
+        max_retention_seconds
+    );
+    
+    Ok(())
+}
+
+/// Register (or unregister) a custom storage scheme records may point into
+pub fn set_storage_scheme(
+    ctx: Context<UpdateConfig>,
+    scheme: String,
+    registered: bool,
+) -> Result<()> {
+    require!(
+        StorageLocator::is_valid_scheme_name(&scheme),
+        CypherMedError::InvalidStorageScheme
+    );
+
+    let config = &mut ctx.accounts.config;
+    let clock = Clock::get()?;
+
+    config.storage_schemes.retain(|existing| *existing != scheme);
+    if registered {
+        require!(
+            config.storage_schemes.len() < ProgramConfig::MAX_STORAGE_SCHEMES,
+            CypherMedError::StorageSchemeLimitReached
+        );
+        config.storage_schemes.push(scheme.clone());
+    }
+    config.updated_at = clock.unix_timestamp;
+
+    msg!("Storage scheme {} registered: {}", scheme, registered);
+    
+    Ok(())
+}
+
+/// Register (or unregister) a wallet allowed to use break-glass access
+///
+/// Unregistering keeps the responder's counters, so re-registering does not
+/// reset its rate limit.
+pub fn set_emergency_responder(
+    ctx: Context<SetEmergencyResponder>,
+    responder: Pubkey,
+    registered: bool,
+) -> Result<()> {
+    let rate_limit = &mut ctx.accounts.rate_limit;
+    if rate_limit.responder == Pubkey::default() {
+        rate_limit.responder = responder;
+        rate_limit.bump = ctx.bumps.rate_limit;
+    }
+    rate_limit.registered = registered;
+
+    msg!("Emergency responder {} registered: {}", responder, registered);
+    
+    Ok(())
+}
+
+fn validate_emergency_limits(
+    window_seconds: i64,
+    soft_threshold: u32,
+    hard_cap: u32,
+) -> Result<()> {
+    require!(window_seconds > 0, CypherMedError::InvalidRateLimitConfig);
+    require!(soft_threshold > 0, CypherMedError::InvalidRateLimitConfig);
+    require!(
+        soft_threshold <= hard_cap,
+        CypherMedError::InvalidRateLimitConfig
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = ProgramConfig::LEN,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ CypherMedError::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::Cyphermed>,
+
+    /// Only the program's upgrade authority may create the config
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ CypherMedError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(responder: Pubkey)]
+pub struct SetEmergencyResponder<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = EmergencyRateLimit::LEN,
+        seeds = [b"emergency_rate", responder.as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, EmergencyRateLimit>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub authority: Signer<'info>,
+}
@@ -0,0 +1,261 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::CypherMedError;
+use crate::audit::*;
+use crate::instructions::legal_hold::require_deletable;
+use crate::instructions::key_rotation::drop_rekey;
+
+/// Hard delete a medical record (right to erasure)
+///
+/// Closes the record, every `RecordVersion` snapshot of it, every
+/// `AmendmentRequest` filed against it and its remaining attachments,
+/// refunding the rent to the patient. `remaining_accounts` holds the version
+/// accounts in order (versions 1 to `record.version - 1`), then the amendment
+/// requests in order (indexes 0 to `record.amendment_count - 1`), then the
+/// `record.attachment_count` attachments in any order, so no prior content,
+/// correction text or attached blob reference survives. An outstanding
+/// `PendingDeletion` is closed as well, refunding its requester. A
+/// `RecordTombstone` keeps only the address, type, time and reason code; the
+/// record id cannot be reused afterwards.
+pub fn purge_record<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PurgeRecord<'info>>,
+    reason: ErasureReason,
+) -> Result<()> {
+    drop_rekey(&mut ctx.accounts.record, ctx.accounts.key_rotation.as_mut())?;
+
+    let patient = &mut ctx.accounts.patient;
+    let record = &ctx.accounts.record;
+    let clock = Clock::get()?;
+
+    require!(patient.is_active, CypherMedError::PatientInactive);
+    require_deletable(
+        &ctx.accounts.config,
+        record,
+        &ctx.accounts.legal_hold,
+        &ctx.accounts.record_type_definition,
+        clock.unix_timestamp,
+    )?;
+
+    // Close every snapshot of the record's previous content
+    let version_count = record.version.saturating_sub(1) as usize;
+    let amendment_count = record.amendment_count as usize;
+    let attachment_count = record.attachment_count as usize;
+    require!(
+        ctx.remaining_accounts.len() == version_count + amendment_count + attachment_count,
+        CypherMedError::RecordVersionsMissing
+    );
+    let (versions, rest) = ctx.remaining_accounts.split_at(version_count);
+    let (amendments, attachments) = rest.split_at(amendment_count);
+    for (index, info) in versions.iter().enumerate() {
+        let (expected, _) = Pubkey::find_program_address(
+            &[
+                b"record_version",
+                record.key().as_ref(),
+                &(index as u32 + 1).to_le_bytes(),
+            ],
+            ctx.program_id,
+        );
+        require_keys_eq!(info.key(), expected, CypherMedError::InvalidRecordVersion);
+
+        let version: Account<'info, RecordVersion> = Account::try_from(info)?;
+        version.close(ctx.accounts.authority.to_account_info())?;
+    }
+
+    // Close the amendment requests, which carry the patient's and provider's statements
+    for (index, info) in amendments.iter().enumerate() {
+        let (expected, _) = Pubkey::find_program_address(
+            &[
+                b"amendment",
+                record.key().as_ref(),
+                &(index as u32).to_le_bytes(),
+            ],
+            ctx.program_id,
+        );
+        require_keys_eq!(info.key(), expected, CypherMedError::InvalidAmendmentRequest);
+
+        let amendment: Account<'info, AmendmentRequest> = Account::try_from(info)?;
+        amendment.close(ctx.accounts.authority.to_account_info())?;
+    }
+
+    // Close the attachments; a soft-deleted record cannot have them removed one by one
+    for info in attachments.iter() {
+        let attachment: Account<'info, RecordAttachment> = Account::try_from(info)?;
+        let (expected, _) = Pubkey::find_program_address(
+            &[
+                b"attachment",
+                record.key().as_ref(),
+                &attachment.index.to_le_bytes(),
+            ],
+            ctx.program_id,
+        );
+        require_keys_eq!(info.key(), expected, CypherMedError::InvalidAttachment);
+        attachment.close(ctx.accounts.authority.to_account_info())?;
+    }
+
+    // An outstanding deletion request holds its reason text
+    let pending_info = ctx.accounts.pending_deletion.to_account_info();
+    if !pending_info.data_is_empty() {
+        require_keys_eq!(*pending_info.owner, crate::ID, CypherMedError::Unauthorized);
+        let requested_by = {
+            let data = pending_info.try_borrow_data()?;
+            PendingDeletion::try_deserialize(&mut &data[..])?.requested_by
+        };
+        let requester = ctx
+            .accounts
+            .deletion_requester
+            .as_ref()
+            .ok_or(CypherMedError::DeletionRequesterRequired)?;
+        require_keys_eq!(requester.key(), requested_by, CypherMedError::Unauthorized);
+        // Same steps as Anchor's `close` constraint
+        let requester = requester.to_account_info();
+        let refund = pending_info.lamports();
+        **requester.try_borrow_mut_lamports()? = requester.lamports().checked_add(refund).unwrap();
+        **pending_info.try_borrow_mut_lamports()? = 0;
+        pending_info.assign(&System::id());
+        pending_info.resize(0)?;
+    }
+
+    let tombstone = &mut ctx.accounts.tombstone;
+    tombstone.record = record.key();
+    tombstone.patient = patient.key();
+    tombstone.record_type = record.record_type;
+    tombstone.purged_at = clock.unix_timestamp;
+    tombstone.reason = reason;
+    tombstone.bump = ctx.bumps.tombstone;
+
+    // The audit entry proves the erasure without repeating any content
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::Purge,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Purged: {:?}", reason)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!("Record {} purged ({:?})", record.key(), reason);
+
+    emit!(RecordPurgedEvent {
+        record: record.key(),
+        patient: patient.key(),
+        record_type: record.record_type,
+        reason,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PurgeRecord<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", authority.key().as_ref()],
+        bump = patient.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub patient: Account<'info, Patient>,
+
+    /// Closed on exit; the account is reassigned and its data truncated
+    #[account(
+        mut,
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+        close = authority,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// CHECK: Legal hold PDA for the record; must be empty (no hold in place)
+    #[account(
+        seeds = [b"legal_hold", record.key().as_ref()],
+        bump,
+    )]
+    pub legal_hold: UncheckedAccount<'info>,
+
+    /// CHECK: Definition PDA for the record's type; empty for a built-in type without one
+    #[account(
+        seeds = [b"record_type".as_ref(), &record.record_type.to_le_bytes()],
+        bump,
+    )]
+    pub record_type_definition: UncheckedAccount<'info>,
+
+    /// Patient's key rotation (required when the record is marked for re-key)
+    #[account(
+        mut,
+        seeds = [b"key_rotation", patient.key().as_ref()],
+        bump = key_rotation.bump,
+    )]
+    pub key_rotation: Option<Account<'info, KeyRotation>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = RecordTombstone::LEN,
+        seeds = [b"tombstone", record.key().as_ref()],
+        bump
+    )]
+    pub tombstone: Account<'info, RecordTombstone>,
+
+    /// CHECK: Pending deletion PDA for the record; closed if a request is outstanding
+    #[account(
+        mut,
+        seeds = [b"pending_deletion", record.key().as_ref()],
+        bump,
+    )]
+    pub pending_deletion: UncheckedAccount<'info>,
+
+    /// CHECK: Requester of an outstanding deletion, refunded its rent; checked against the request
+    #[account(mut)]
+    pub deletion_requester: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct RecordPurgedEvent {
+    pub record: Pubkey,
+    pub patient: Pubkey,
+    pub record_type: RecordTypeCode,
+    pub reason: ErasureReason,
+    pub timestamp: i64,
+}
@@ -0,0 +1,135 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::CypherMedError;
+
+/// Register or update a record type definition (config authority only)
+pub fn register_record_type(
+    ctx: Context<RegisterRecordType>,
+    code: RecordTypeCode,
+    name: String,
+    sensitivity: SensitivityTier,
+    default_min_retention_seconds: Option<i64>,
+    default_max_retention_seconds: Option<i64>,
+) -> Result<()> {
+    require!(
+        !name.is_empty()
+            && name.len() <= RecordTypeDefinition::MAX_NAME_LEN
+            && name.chars().all(|c| c.is_ascii_graphic() || c == ' '),
+        CypherMedError::InvalidRecordTypeName
+    );
+    let positive = |period: Option<i64>| period.is_none_or(|seconds| seconds > 0);
+    require!(
+        positive(default_min_retention_seconds) && positive(default_max_retention_seconds),
+        CypherMedError::InvalidRetentionPolicy
+    );
+    if let (Some(min), Some(max)) = (default_min_retention_seconds, default_max_retention_seconds) {
+        require!(min <= max, CypherMedError::InvalidRetentionPolicy);
+    }
+
+    let definition = &mut ctx.accounts.record_type_definition;
+    let clock = Clock::get()?;
+
+    if definition.registered_at == 0 {
+        definition.registered_at = clock.unix_timestamp;
+    }
+    definition.code = code;
+    definition.name = name.clone();
+    definition.sensitivity = sensitivity;
+    definition.default_min_retention_seconds = default_min_retention_seconds;
+    definition.default_max_retention_seconds = default_max_retention_seconds;
+    definition.is_active = true;
+    definition.updated_at = clock.unix_timestamp;
+    definition.bump = ctx.bumps.record_type_definition;
+
+    msg!("Record type {} registered as {} ({:?})", code, name, sensitivity);
+
+    emit!(RecordTypeRegisteredEvent {
+        code,
+        name,
+        sensitivity,
+        builtin: RecordType::from_code(code).is_some(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Retire a record type so no new records use it (config authority only)
+///
+/// Existing records, grants and retention rules are unaffected.
+pub fn retire_record_type(ctx: Context<RetireRecordType>) -> Result<()> {
+    let definition = &mut ctx.accounts.record_type_definition;
+    let clock = Clock::get()?;
+
+    require!(definition.is_active, CypherMedError::RecordTypeRetired);
+    definition.is_active = false;
+    definition.updated_at = clock.unix_timestamp;
+
+    msg!("Record type {} retired", definition.code);
+
+    emit!(RecordTypeRetiredEvent {
+        code: definition.code,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(code: RecordTypeCode)]
+pub struct RegisterRecordType<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RecordTypeDefinition::LEN,
+        seeds = [b"record_type".as_ref(), &code.to_le_bytes()],
+        bump
+    )]
+    pub record_type_definition: Account<'info, RecordTypeDefinition>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RetireRecordType<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ CypherMedError::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"record_type".as_ref(), &record_type_definition.code.to_le_bytes()],
+        bump = record_type_definition.bump,
+    )]
+    pub record_type_definition: Account<'info, RecordTypeDefinition>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct RecordTypeRegisteredEvent {
+    pub code: RecordTypeCode,
+    pub name: String,
+    pub sensitivity: SensitivityTier,
+    pub builtin: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecordTypeRetiredEvent {
+    pub code: RecordTypeCode,
+    pub timestamp: i64,
+}
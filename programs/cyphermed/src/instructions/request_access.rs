@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::CypherMedError;
+use crate::audit::*;
 
 /// Request access to patient records
 pub fn request_access(
@@ -8,8 +9,9 @@ pub fn request_access(
     requester_role: Role,
     reason: Option<String>,
     custom_expiration: Option<i64>,
+    encryption_key_id: Option<u32>,
 ) -> Result<()> {
-    let patient = &ctx.accounts.patient;
+    let patient = &mut ctx.accounts.patient;
     let access_request = &mut ctx.accounts.access_request;
     let clock = Clock::get()?;
 
@@ -50,6 +52,7 @@ pub fn request_access(
     access_request.requester = ctx.accounts.requester.key();
     access_request.requester_role = requester_role;
     access_request.reason = reason.clone();
+    access_request.encryption_key_id = encryption_key_id;
     access_request.requested_at = clock.unix_timestamp;
     access_request.expires_at = expires_at;
     access_request.status = RequestStatus::Pending;
@@ -59,6 +62,32 @@ pub fn request_access(
     access_request.notification_sent = false; // Frontend will handle sending notification
     access_request.bump = ctx.bumps.access_request;
 
+    // Log the request in the patient's audit trail
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.requester.key(),
+        accessor_role: requester_role,
+        action: AccessAction::RequestAccess,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Access requested by {}", ctx.accounts.requester.key())),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
     msg!(
         "Access request created: {} requesting access to patient {}",
         ctx.accounts.requester.key(),
@@ -70,7 +99,7 @@ pub fn request_access(
         request: access_request.key(),
         patient: patient.key(),
         requester: ctx.accounts.requester.key(),
-        reason: reason,
+        reason,
         expires_at,
     });
     
@@ -80,6 +109,7 @@ pub fn request_access(
 #[derive(Accounts)]
 pub struct RequestAccess<'info> {
     #[account(
+        mut,
         seeds = [b"patient", patient.authority.as_ref()],
         bump = patient.bump,
     )]
@@ -98,6 +128,21 @@ pub struct RequestAccess<'info> {
     )]
     pub access_request: Account<'info, AccessRequest>,
 
+    #[account(
+        init,
+        payer = requester,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
     #[account(mut)]
     pub requester: Signer<'info>,
 
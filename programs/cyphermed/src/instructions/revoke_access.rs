@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::CypherMedError;
+use crate::audit::*;
+use crate::instructions::key_envelope::close_key_envelope;
 
 /// Revoke access from a healthcare provider
 pub fn revoke_access(ctx: Context<RevokeAccess>) -> Result<()> {
@@ -25,8 +27,45 @@ pub fn revoke_access(ctx: Context<RevokeAccess>) -> Result<()> {
     access_grant.revoked_by = Some(ctx.accounts.authority.key());
     access_grant.revoked_at = Some(clock.unix_timestamp);
 
+    // The former grantee must not be able to fetch the data key any more
+    let envelope_closed = close_key_envelope(
+        &ctx.accounts.key_envelope,
+        &ctx.accounts.authority,
+        ctx.accounts.grantee_encryption_key.as_deref_mut(),
+    )?;
+
     patient.updated_at = clock.unix_timestamp;
 
+    // Log the revocation in the audit trail
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: None,
+        accessor: ctx.accounts.authority.key(),
+        accessor_role: Role::Patient,
+        action: AccessAction::RevokeAccess,
+        record_type: None,
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!(
+            "Revoked access from {}{}",
+            access_grant.provider,
+            if envelope_closed { " (key envelope closed)" } else { "" }
+        )),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
     msg!(
         "Access revoked from provider: {} by patient: {}",
         access_grant.provider,
@@ -53,6 +92,39 @@ pub struct RevokeAccess<'info> {
     )]
     pub access_grant: Account<'info, AccessGrant>,
 
+    /// CHECK: Key envelope PDA for the grant; closed if one was issued
+    #[account(
+        mut,
+        seeds = [b"key_envelope", patient.key().as_ref(), access_grant.provider.as_ref()],
+        bump,
+    )]
+    pub key_envelope: UncheckedAccount<'info>,
+
+    /// Grantee's encryption key registration (required when an envelope is closed)
+    #[account(
+        mut,
+        seeds = [b"encryption_key", access_grant.provider.as_ref()],
+        bump = grantee_encryption_key.bump,
+    )]
+    pub grantee_encryption_key: Option<Account<'info, EncryptionKey>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
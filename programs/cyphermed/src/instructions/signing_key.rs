@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+/// Register (or rotate) the Ed25519 key a provider attests records with
+///
+/// Rotation only affects future attestations; records keep the key that
+/// signed them in `attested_by`.
+pub fn register_signing_key(
+    ctx: Context<RegisterSigningKey>,
+    signing_key: Pubkey,
+) -> Result<()> {
+    let registration = &mut ctx.accounts.provider_signing_key;
+    let clock = Clock::get()?;
+
+    let previous_key = if registration.provider == Pubkey::default() {
+        None
+    } else {
+        Some(registration.signing_key)
+    };
+
+    registration.provider = ctx.accounts.provider.key();
+    registration.signing_key = signing_key;
+    registration.registered_at = clock.unix_timestamp;
+    registration.bump = ctx.bumps.provider_signing_key;
+
+    msg!(
+        "Signing key {} registered for provider {}",
+        signing_key,
+        registration.provider
+    );
+
+    emit!(SigningKeyRegisteredEvent {
+        provider: registration.provider,
+        signing_key,
+        previous_key,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RegisterSigningKey<'info> {
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = ProviderSigningKey::LEN,
+        seeds = [b"signing_key", provider.key().as_ref()],
+        bump
+    )]
+    pub provider_signing_key: Account<'info, ProviderSigningKey>,
+
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct SigningKeyRegisteredEvent {
+    pub provider: Pubkey,
+    pub signing_key: Pubkey,
+    pub previous_key: Option<Pubkey>,
+    pub timestamp: i64,
+}
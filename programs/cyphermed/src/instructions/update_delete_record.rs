@@ -1,80 +1,98 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::CypherMedError;
+use crate::audit::*;
+use crate::authorization::*;
+use crate::attestation::attest_record;
+use crate::instructions::legal_hold::require_deletable;
+use crate::instructions::key_rotation::{complete_rekey, drop_rekey, resume_rekey};
 
 /// Update an existing medical record
 pub fn update_record(
     ctx: Context<UpdateRecord>,
-    new_data_hash: Option<String>,
+    new_data_hash: Option<ContentHash>,
+    new_storage: Option<StorageLocator>,
     new_metadata: Option<String>,
+    new_clinical_codes: Option<Vec<ClinicalCode>>,
     update_note: String,
 ) -> Result<()> {
-    let patient = &ctx.accounts.patient;
+    let patient = &mut ctx.accounts.patient;
     let record = &mut ctx.accounts.record;
     let clock = Clock::get()?;
 
-    // Verify patient and record are active
-    require!(patient.is_active, CypherMedError::PatientInactive);
-    require!(record.is_active, CypherMedError::RecordInactive);
-
-    // Check authorization: only creator or patient can update
-    let is_patient = ctx.accounts.updater.key() == patient.authority;
-    let is_creator = ctx.accounts.updater.key() == record.created_by;
-
-    // If not patient or creator, check for modify permission via access grant
-    if !is_patient && !is_creator {
-        let access_grant = ctx.accounts.access_grant.as_ref()
-            .ok_or(CypherMedError::AccessDenied)?;
-        
-        require!(access_grant.is_active, CypherMedError::AccessGrantRevoked);
-        require!(
-            !crate::utils::is_grant_expired(access_grant.expires_at, clock.unix_timestamp),
-            CypherMedError::AccessGrantExpired
-        );
-        require!(access_grant.can_modify, CypherMedError::AccessDenied);
+    // Check authorization: creator or patient, otherwise modify permission via grant
+    let mut auth = authorize(
+        patient,
+        &ctx.accounts.updater.key(),
+        ctx.accounts.access_grant.as_deref(),
+        RecordOperation::Modify,
+        RecordTarget::Existing(record),
+        clock.unix_timestamp,
+    );
+    // The patient may re-encrypt a provider's record flagged during a key
+    // rotation, as long as only the ciphertext changes
+    let is_rekey = record.needs_rekey && new_data_hash.is_some();
+    let content_only = new_metadata.is_none() && new_clinical_codes.is_none();
+    if auth.decision == AccessDecision::AmendmentRequired && is_rekey && content_only {
+        auth.decision = AccessDecision::Allowed;
     }
+    auth.decision.require_allowed()?;
 
     // Validate update note
     require!(!update_note.is_empty(), CypherMedError::UpdateNoteRequired);
     require!(update_note.len() <= 500, CypherMedError::UpdateNoteTooLong);
 
-    // Update record fields if provided
-    if let Some(hash) = new_data_hash {
-        require!(hash.len() <= 64, CypherMedError::DataHashTooLong);
-        record.data_hash = hash;
-    }
-
-    if let Some(meta) = new_metadata {
-        require!(meta.len() <= 200, CypherMedError::MetadataTooLong);
-        record.metadata = Some(meta);
+    apply_content_update(
+        record,
+        &mut ctx.accounts.record_version,
+        ctx.bumps.record_version,
+        ctx.accounts.config.as_deref(),
+        ctx.accounts.updater.key(),
+        &update_note,
+        ContentUpdate {
+            data_hash: new_data_hash,
+            key_epoch: patient.data_key_epoch,
+            storage: new_storage,
+            metadata: new_metadata,
+            clinical_codes: new_clinical_codes,
+        },
+        clock.unix_timestamp,
+    )?;
+    if is_rekey {
+        complete_rekey(record, ctx.accounts.key_rotation.as_mut())?;
     }
-
-    // Update timestamp
-    record.modified_at = clock.unix_timestamp;
+    attest_record(
+        record,
+        &ctx.accounts.provider_signing_key,
+        ctx.accounts.instructions_sysvar.as_ref(),
+        clock.unix_timestamp,
+    )?;
 
     // Create audit log for the update
-    let audit = &mut ctx.accounts.audit_log;
-    audit.patient = patient.key();
-    audit.record = record.key();
-    audit.accessor = ctx.accounts.updater.key();
-    audit.accessor_role = if is_patient {
-        Role::Patient
-    } else if let Some(grant) = &ctx.accounts.access_grant {
-        grant.role
-    } else {
-        // Creator (doctor/hospital)
-        Role::Doctor // Default, could be passed as param
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.updater.key(),
+        accessor_role: auth.role,
+        action: AccessAction::Modify,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Updated to version {}", record.version)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
     };
-    audit.action = AccessAction::Modify;
-    audit.record_type = record.record_type;
-    audit.timestamp = clock.unix_timestamp;
-    audit.success = true;
-    audit.failure_reason = None;
-    audit.is_emergency = false;
-    audit.emergency_justification = None;
-    audit.client_info = None;
-    audit.metadata = Some(format!("Update: {}", update_note));
-    audit.bump = ctx.bumps.audit_log;
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
 
     msg!(
         "Record {} updated by {}. Note: {}",
@@ -88,60 +106,207 @@ pub fn update_record(
         patient: patient.key(),
         updater: ctx.accounts.updater.key(),
         update_note,
+        version: record.version,
+        clinical_codes: record.clinical_codes.clone(),
         timestamp: clock.unix_timestamp,
     });
     
     Ok(())
 }
 
+/// New content for a record; `None` fields keep their current value
+pub(crate) struct ContentUpdate {
+    pub data_hash: Option<ContentHash>,
+    /// Data-key epoch a new `data_hash` was encrypted under
+    pub key_epoch: u32,
+    pub storage: Option<StorageLocator>,
+    pub metadata: Option<String>,
+    pub clinical_codes: Option<Vec<ClinicalCode>>,
+}
+
+/// Snapshot the record's current content into `version`, then apply `update`
+/// and bump the record to the next version
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_content_update(
+    record: &mut Account<MedicalRecord>,
+    version: &mut RecordVersion,
+    version_bump: u8,
+    config: Option<&ProgramConfig>,
+    updated_by: Pubkey,
+    update_note: &str,
+    update: ContentUpdate,
+    now: i64,
+) -> Result<()> {
+    // Snapshot the current content before overwriting it
+    version.record = record.key();
+    version.version = record.version;
+    version.data_hash = record.data_hash;
+    version.key_epoch = record.key_epoch;
+    version.attested_by = record.attested_by;
+    version.storage = record.storage.clone();
+    version.metadata = record.metadata.clone();
+    version.clinical_codes = record.clinical_codes.clone();
+    version.valid_from = record.content_updated_at;
+    version.superseded_at = now;
+    version.updated_by = updated_by;
+    version.update_note = update_note.to_string();
+    version.bump = version_bump;
+
+    // Update record fields if provided
+    if let Some(hash) = update.data_hash {
+        record.data_hash = hash;
+        record.key_epoch = update.key_epoch;
+    }
+
+    if let Some(storage) = update.storage {
+        storage.validate(config)?;
+        record.storage = Some(storage);
+    }
+
+    if let Some(meta) = update.metadata {
+        require!(meta.len() <= 200, CypherMedError::MetadataTooLong);
+        record.metadata = Some(meta);
+    }
+
+    if let Some(codes) = update.clinical_codes {
+        ClinicalCode::validate_list(&codes)?;
+        record.clinical_codes = codes;
+    }
+
+    // Bump version and timestamp; an attestation covers one version only
+    record.version = record.version.checked_add(1).unwrap();
+    record.modified_at = now;
+    record.content_updated_at = now;
+    record.attested = false;
+    record.attested_by = None;
+    Ok(())
+}
+
 /// Soft delete a medical record
 pub fn delete_record(
     ctx: Context<DeleteRecord>,
     deletion_reason: String,
 ) -> Result<()> {
-    let patient = &ctx.accounts.patient;
+    let patient = &mut ctx.accounts.patient;
     let record = &mut ctx.accounts.record;
     let clock = Clock::get()?;
 
-    // Verify patient is active
-    require!(patient.is_active, CypherMedError::PatientInactive);
+    // Patient, creator, or a grant carrying the delete permission
+    let auth = authorize(
+        patient,
+        &ctx.accounts.deleter.key(),
+        ctx.accounts.access_grant.as_deref(),
+        RecordOperation::Delete,
+        RecordTarget::Existing(record),
+        clock.unix_timestamp,
+    );
+    auth.decision.require_allowed()?;
+    require_deletable(
+        &ctx.accounts.config,
+        record,
+        &ctx.accounts.legal_hold,
+        &ctx.accounts.record_type_definition,
+        clock.unix_timestamp,
+    )?;
 
-    // Record must be active to delete
-    require!(record.is_active, CypherMedError::RecordInactive);
+    // Validate deletion reason
+    require!(!deletion_reason.is_empty(), CypherMedError::DeletionReasonRequired);
+    require!(deletion_reason.len() <= 300, CypherMedError::DeletionReasonTooLong);
 
-    // Only creator or patient can delete
+    // Provider deletions wait for the patient's co-signature if the patient asked for it
     let is_patient = ctx.accounts.deleter.key() == patient.authority;
-    let is_creator = ctx.accounts.deleter.key() == record.created_by;
+    let cosign_window = patient.deletion_cosign_window.filter(|_| !is_patient);
+    if let Some(window) = cosign_window {
+        let pending = ctx
+            .accounts
+            .pending_deletion
+            .as_mut()
+            .ok_or(CypherMedError::PendingDeletionRequired)?;
+        pending.record = record.key();
+        pending.patient = patient.key();
+        pending.requested_by = ctx.accounts.deleter.key();
+        pending.requester_role = auth.role;
+        pending.reason = deletion_reason.clone();
+        pending.requested_at = clock.unix_timestamp;
+        pending.executes_at = clock.unix_timestamp.checked_add(window).unwrap();
+        pending.bump = ctx.bumps.pending_deletion.unwrap_or_default();
+
+        let entry = AuditLog {
+            patient: patient.key(),
+            sequence: 0,
+            prev_hash: [0; 32],
+            record: Some(record.key()),
+            accessor: ctx.accounts.deleter.key(),
+            accessor_role: auth.role,
+            action: AccessAction::RequestDeletion,
+            record_type: Some(record.record_type),
+            timestamp: clock.unix_timestamp,
+            success: true,
+            decision: AccessDecision::Allowed,
+            is_emergency: false,
+            emergency_justification: None,
+            client_info: None,
+            metadata: Some(format!("Deletion requested, co-sign window {}s", window)),
+            bump: ctx.bumps.audit_log.unwrap_or_default(),
+        };
+        write_audit_entry(
+            patient,
+            ctx.accounts.audit_log.as_mut(),
+            &ctx.accounts.compressed_audit,
+            entry,
+        )?;
+
+        msg!(
+            "Deletion of record {} requested by {}; awaiting patient co-signature",
+            record.key(),
+            ctx.accounts.deleter.key()
+        );
+
+        emit!(DeletionRequestedEvent {
+            record: record.key(),
+            patient: patient.key(),
+            requested_by: ctx.accounts.deleter.key(),
+            reason: deletion_reason,
+            executes_at: pending.executes_at,
+            timestamp: clock.unix_timestamp,
+        });
 
+        return Ok(());
+    }
     require!(
-        is_patient || is_creator,
-        CypherMedError::Unauthorized
+        ctx.accounts.pending_deletion.is_none(),
+        CypherMedError::PendingDeletionNotAllowed
     );
 
-    // Validate deletion reason
-    require!(!deletion_reason.is_empty(), CypherMedError::DeletionReasonRequired);
-    require!(deletion_reason.len() <= 300, CypherMedError::DeletionReasonTooLong);
-
     // Soft delete - mark as inactive
-    record.is_active = false;
-    record.modified_at = clock.unix_timestamp;
+    drop_rekey(record, ctx.accounts.key_rotation.as_mut())?;
+    record.soft_delete(ctx.accounts.deleter.key(), clock.unix_timestamp);
 
     // Create audit log for deletion
-    let audit = &mut ctx.accounts.audit_log;
-    audit.patient = patient.key();
-    audit.record = record.key();
-    audit.accessor = ctx.accounts.deleter.key();
-    audit.accessor_role = if is_patient { Role::Patient } else { Role::Doctor };
-    audit.action = AccessAction::Delete;
-    audit.record_type = record.record_type;
-    audit.timestamp = clock.unix_timestamp;
-    audit.success = true;
-    audit.failure_reason = None;
-    audit.is_emergency = false;
-    audit.emergency_justification = None;
-    audit.client_info = None;
-    audit.metadata = Some(format!("Deleted: {}", deletion_reason));
-    audit.bump = ctx.bumps.audit_log;
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.deleter.key(),
+        accessor_role: auth.role,
+        action: AccessAction::Delete,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some("Soft deleted".to_string()),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
 
     msg!(
         "Record {} soft-deleted by {}. Reason: {}",
@@ -161,9 +326,101 @@ pub fn delete_record(
     Ok(())
 }
 
+/// Restore a soft-deleted medical record
+///
+/// The patient can always restore. The original deleter can within the grace
+/// period, provided they could still delete the record: the creator always,
+/// anyone else only through an active grant with delete permission.
+pub fn restore_record(
+    ctx: Context<RestoreRecord>,
+    reason: RestoreReason,
+) -> Result<()> {
+    let patient = &mut ctx.accounts.patient;
+    let record = &mut ctx.accounts.record;
+    let clock = Clock::get()?;
+
+    require!(patient.is_active, CypherMedError::PatientInactive);
+    require!(!record.is_active, CypherMedError::RecordAlreadyActive);
+
+    // The patient can always restore; the original deleter only within the grace period
+    let is_patient = ctx.accounts.restorer.key() == patient.authority;
+    if !is_patient {
+        require!(
+            record.deleted_by == Some(ctx.accounts.restorer.key()),
+            CypherMedError::Unauthorized
+        );
+        let deleted_at = record.deleted_at.unwrap_or_default();
+        require!(
+            clock.unix_timestamp <= deleted_at.saturating_add(MedicalRecord::RESTORE_GRACE_PERIOD_SECONDS),
+            CypherMedError::RestoreGracePeriodExpired
+        );
+    }
+
+    record.is_active = true;
+    record.deleted_by = None;
+    record.deleted_at = None;
+
+    // Checked against the reactivated record; a failure reverts the restore
+    let auth = authorize(
+        patient,
+        &ctx.accounts.restorer.key(),
+        ctx.accounts.access_grant.as_deref(),
+        RecordOperation::Delete,
+        RecordTarget::Existing(record),
+        clock.unix_timestamp,
+    );
+    auth.decision.require_allowed()?;
+
+    resume_rekey(record, patient.data_key_epoch, ctx.accounts.key_rotation.as_mut())?;
+
+    // Create audit log for the restoration
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.restorer.key(),
+        accessor_role: auth.role,
+        action: AccessAction::Restore,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: true,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!("Restored: {:?}", reason)),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    msg!(
+        "Record {} restored by {}. Reason: {:?}",
+        record.key(),
+        ctx.accounts.restorer.key(),
+        reason
+    );
+
+    emit!(RecordRestoredEvent {
+        record: record.key(),
+        patient: patient.key(),
+        restorer: ctx.accounts.restorer.key(),
+        reason,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
 #[derive(Accounts)]
 pub struct UpdateRecord<'info> {
     #[account(
+        mut,
         seeds = [b"patient", patient.authority.as_ref()],
         bump = patient.bump,
     )]
@@ -183,20 +440,59 @@ pub struct UpdateRecord<'info> {
     )]
     pub access_grant: Option<Account<'info, AccessGrant>>,
 
+    /// Program config (required only for custom storage schemes)
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Option<Account<'info, ProgramConfig>>,
+
+    /// Patient's key rotation (required when re-keying a record marked for it)
+    #[account(
+        mut,
+        seeds = [b"key_rotation", patient.key().as_ref()],
+        bump = key_rotation.bump,
+    )]
+    pub key_rotation: Option<Account<'info, KeyRotation>>,
+
+    /// CHECK: Signing key PDA of the updater; if a key is registered the content must be attested
+    #[account(
+        seeds = [b"signing_key", updater.key().as_ref()],
+        bump,
+    )]
+    pub provider_signing_key: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, read to find the Ed25519 attestation
+    #[account(address = solana_sdk_ids::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
     #[account(
         init,
         payer = updater,
         space = AuditLog::LEN,
         seeds = [
             b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(
+        init,
+        payer = updater,
+        space = RecordVersion::LEN,
+        seeds = [
+            b"record_version",
             record.key().as_ref(),
-            updater.key().as_ref(),
-            b"modify",
-            &record.access_count.to_le_bytes()
+            &record.version.to_le_bytes()
         ],
         bump
     )]
-    pub audit_log: Account<'info, AuditLog>,
+    pub record_version: Account<'info, RecordVersion>,
 
     #[account(mut)]
     pub updater: Signer<'info>,
@@ -207,6 +503,7 @@ pub struct UpdateRecord<'info> {
 #[derive(Accounts)]
 pub struct DeleteRecord<'info> {
     #[account(
+        mut,
         seeds = [b"patient", patient.authority.as_ref()],
         bump = patient.bump,
     )]
@@ -219,20 +516,65 @@ pub struct DeleteRecord<'info> {
     )]
     pub record: Account<'info, MedicalRecord>,
 
+    /// Optional access grant (if deleter is not patient or creator)
+    #[account(
+        seeds = [b"access_grant", patient.key().as_ref(), deleter.key().as_ref()],
+        bump,
+    )]
+    pub access_grant: Option<Account<'info, AccessGrant>>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// CHECK: Legal hold PDA for the record; must be empty (no hold in place)
+    #[account(
+        seeds = [b"legal_hold", record.key().as_ref()],
+        bump,
+    )]
+    pub legal_hold: UncheckedAccount<'info>,
+
+    /// CHECK: Definition PDA for the record's type; empty for a built-in type without one
+    #[account(
+        seeds = [b"record_type".as_ref(), &record.record_type.to_le_bytes()],
+        bump,
+    )]
+    pub record_type_definition: UncheckedAccount<'info>,
+
+    /// Patient's key rotation (required when the record is marked for re-key)
+    #[account(
+        mut,
+        seeds = [b"key_rotation", patient.key().as_ref()],
+        bump = key_rotation.bump,
+    )]
+    pub key_rotation: Option<Account<'info, KeyRotation>>,
+
+    /// Required instead of deleting immediately when the patient must co-sign
+    #[account(
+        init,
+        payer = deleter,
+        space = PendingDeletion::LEN,
+        seeds = [b"pending_deletion", record.key().as_ref()],
+        bump
+    )]
+    pub pending_deletion: Option<Account<'info, PendingDeletion>>,
+
     #[account(
         init,
         payer = deleter,
         space = AuditLog::LEN,
         seeds = [
             b"audit",
-            record.key().as_ref(),
-            deleter.key().as_ref(),
-            b"delete",
-            &record.access_count.to_le_bytes()
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
         ],
         bump
     )]
-    pub audit_log: Account<'info, AuditLog>,
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
 
     #[account(mut)]
     pub deleter: Signer<'info>,
@@ -246,6 +588,8 @@ pub struct RecordUpdatedEvent {
     pub patient: Pubkey,
     pub updater: Pubkey,
     pub update_note: String,
+    pub version: u32,
+    pub clinical_codes: Vec<ClinicalCode>,
     pub timestamp: i64,
 }
 
@@ -257,3 +601,74 @@ pub struct RecordDeletedEvent {
     pub reason: String,
     pub timestamp: i64,
 }
+
+#[event]
+pub struct DeletionRequestedEvent {
+    pub record: Pubkey,
+    pub patient: Pubkey,
+    pub requested_by: Pubkey,
+    pub reason: String,
+    pub executes_at: i64,
+    pub timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct RestoreRecord<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", patient.authority.as_ref()],
+        bump = patient.bump,
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        mut,
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    /// Access grant (required if the restorer is neither patient nor creator)
+    #[account(
+        seeds = [b"access_grant", patient.key().as_ref(), restorer.key().as_ref()],
+        bump,
+    )]
+    pub access_grant: Option<Account<'info, AccessGrant>>,
+
+    /// Patient's key rotation (required when the record is on an old data key)
+    #[account(
+        mut,
+        seeds = [b"key_rotation", patient.key().as_ref()],
+        bump = key_rotation.bump,
+    )]
+    pub key_rotation: Option<Account<'info, KeyRotation>>,
+
+    #[account(
+        init,
+        payer = restorer,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub restorer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct RecordRestoredEvent {
+    pub record: Pubkey,
+    pub patient: Pubkey,
+    pub restorer: Pubkey,
+    pub reason: RestoreReason,
+    pub timestamp: i64,
+}
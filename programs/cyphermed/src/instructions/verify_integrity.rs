@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::audit::*;
+use crate::authorization::*;
+
+/// Compare the digest of a retrieved blob against the record's stored hash
+///
+/// The verifier must be allowed to view the record. The outcome is written
+/// to the audit trail and returned as return data; a mismatch is recorded,
+/// not reverted.
+pub fn verify_record_integrity(
+    ctx: Context<VerifyRecordIntegrity>,
+    computed_hash: ContentHash,
+) -> Result<bool> {
+    let patient = &mut ctx.accounts.patient;
+    let record = &ctx.accounts.record;
+    let clock = Clock::get()?;
+
+    let auth = authorize(
+        patient,
+        &ctx.accounts.verifier.key(),
+        ctx.accounts.access_grant.as_deref(),
+        RecordOperation::View,
+        RecordTarget::Existing(record),
+        clock.unix_timestamp,
+    );
+    auth.decision.require_allowed()?;
+
+    let matches = computed_hash == record.data_hash;
+
+    let entry = AuditLog {
+        patient: patient.key(),
+        sequence: 0,
+        prev_hash: [0; 32],
+        record: Some(record.key()),
+        accessor: ctx.accounts.verifier.key(),
+        accessor_role: auth.role,
+        action: AccessAction::VerifyIntegrity,
+        record_type: Some(record.record_type),
+        timestamp: clock.unix_timestamp,
+        success: matches,
+        decision: AccessDecision::Allowed,
+        is_emergency: false,
+        emergency_justification: None,
+        client_info: None,
+        metadata: Some(format!(
+            "Integrity {} against version {} ({:?})",
+            if matches { "verified" } else { "mismatch" },
+            record.version,
+            computed_hash.algorithm
+        )),
+        bump: ctx.bumps.audit_log.unwrap_or_default(),
+    };
+    write_audit_entry(
+        patient,
+        ctx.accounts.audit_log.as_mut(),
+        &ctx.accounts.compressed_audit,
+        entry,
+    )?;
+
+    if !matches {
+        msg!("⚠️ Integrity mismatch on record {}", record.key());
+    }
+
+    emit!(IntegrityVerifiedEvent {
+        record: record.key(),
+        verifier: ctx.accounts.verifier.key(),
+        version: record.version,
+        matches,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(matches)
+}
+
+#[derive(Accounts)]
+pub struct VerifyRecordIntegrity<'info> {
+    #[account(
+        mut,
+        seeds = [b"patient", patient.authority.as_ref()],
+        bump = patient.bump,
+    )]
+    pub patient: Account<'info, Patient>,
+
+    #[account(
+        seeds = [b"record", patient.key().as_ref(), record.record_id.as_bytes()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, MedicalRecord>,
+
+    /// Optional access grant (required if verifier is not the patient)
+    #[account(
+        seeds = [b"access_grant", patient.key().as_ref(), verifier.key().as_ref()],
+        bump,
+    )]
+    pub access_grant: Option<Account<'info, AccessGrant>>,
+
+    #[account(
+        init,
+        payer = verifier,
+        space = AuditLog::LEN,
+        seeds = [
+            b"audit",
+            patient.key().as_ref(),
+            &patient.audit_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub compressed_audit: CompressedAudit<'info>,
+
+    #[account(mut)]
+    pub verifier: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct IntegrityVerifiedEvent {
+    pub record: Pubkey,
+    pub verifier: Pubkey,
+    pub version: u32,
+    pub matches: bool,
+    pub timestamp: i64,
+}
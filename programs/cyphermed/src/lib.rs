@@ -6,6 +6,9 @@ pub mod state;
 pub mod instructions;
 pub mod errors;
 pub mod utils;
+pub mod audit;
+pub mod authorization;
+pub mod attestation;
 
 use instructions::*;
 use state::*;
@@ -28,24 +31,45 @@ pub mod cyphermed {
     pub fn create_record(
         ctx: Context<CreateRecord>,
         record_id: String,
-        record_type: RecordType,
-        data_hash: String,
-        storage_cid: Option<String>,
+        record_type: RecordTypeCode,
+        data_hash: ContentHash,
+        storage: Option<StorageLocator>,
         metadata: Option<String>,
+        clinical_codes: Vec<ClinicalCode>,
     ) -> Result<()> {
-        instructions::create_record(ctx, record_id, record_type, data_hash, storage_cid, metadata)
+        instructions::create_record(
+            ctx,
+            record_id,
+            record_type,
+            data_hash,
+            storage,
+            metadata,
+            clinical_codes,
+        )
+    }
+
+    /// Register or rotate the Ed25519 key a provider attests records with
+    pub fn register_signing_key(
+        ctx: Context<RegisterSigningKey>,
+        signing_key: Pubkey,
+    ) -> Result<()> {
+        instructions::register_signing_key(ctx, signing_key)
     }
 
     /// Grant access to a healthcare provider
+    #[allow(clippy::too_many_arguments)]
     pub fn grant_access(
         ctx: Context<GrantAccess>,
         role: Role,
-        allowed_record_types: Vec<RecordType>,
+        allowed_record_types: Vec<RecordTypeCode>,
         expires_at: Option<i64>,
         can_create: bool,
         can_modify: bool,
         can_view: bool,
+        can_delete: bool,
         reason: Option<String>,
+        wrapped_key: Option<WrappedKey>,
+        encryption_key_id: Option<u32>,
     ) -> Result<()> {
         instructions::grant_access(
             ctx,
@@ -55,7 +79,10 @@ pub mod cyphermed {
             can_create,
             can_modify,
             can_view,
+            can_delete,
             reason,
+            wrapped_key,
+            encryption_key_id,
         )
     }
 
@@ -64,14 +91,95 @@ pub mod cyphermed {
         instructions::revoke_access(ctx)
     }
 
-    /// Access (view) a medical record
+    /// Register a wallet-signed X25519 key, rotating out the current one
+    pub fn register_encryption_key(
+        ctx: Context<RegisterEncryptionKey>,
+        x25519_public_key: [u8; 32],
+    ) -> Result<()> {
+        instructions::register_encryption_key(ctx, x25519_public_key)
+    }
+
+    /// Revoke one of the wallet's encryption keys
+    pub fn revoke_encryption_key(
+        ctx: Context<RevokeEncryptionKey>,
+        key_id: u32,
+    ) -> Result<()> {
+        instructions::revoke_encryption_key(ctx, key_id)
+    }
+
+    /// Share the patient's data key with an existing grantee
+    pub fn issue_key_envelope(
+        ctx: Context<IssueKeyEnvelope>,
+        wrapped_key: WrappedKey,
+        encryption_key_id: Option<u32>,
+    ) -> Result<()> {
+        instructions::issue_key_envelope(ctx, wrapped_key, encryption_key_id)
+    }
+
+    /// Rotate the patient's data key after a grantee loses access
+    pub fn start_key_rotation(
+        ctx: Context<StartKeyRotation>,
+        revoked_grantee: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::start_key_rotation(ctx, revoked_grantee)
+    }
+
+    /// Flag records still encrypted under the old data key for re-encryption
+    pub fn mark_records_for_rekey<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MarkRecordsForRekey<'info>>,
+    ) -> Result<()> {
+        instructions::mark_records_for_rekey(ctx)
+    }
+
+    /// Finish a key rotation once no marked records are pending
+    pub fn complete_key_rotation(ctx: Context<FinishKeyRotation>) -> Result<()> {
+        instructions::complete_key_rotation(ctx)
+    }
+
+    /// Abandon a key rotation, leaving pending records on their old epoch
+    pub fn cancel_key_rotation(ctx: Context<FinishKeyRotation>) -> Result<()> {
+        instructions::cancel_key_rotation(ctx)
+    }
+
+    /// Escrow an emergency decryption key as Shamir shares held by custodians
+    pub fn configure_key_escrow(
+        ctx: Context<ConfigureKeyEscrow>,
+        threshold: u8,
+        shares: Vec<EscrowShareInput>,
+    ) -> Result<()> {
+        instructions::configure_key_escrow(ctx, threshold, shares)
+    }
+
+    /// Release a custodian's share to the responder of an emergency access
+    pub fn release_escrow_share(
+        ctx: Context<ReleaseEscrowShare>,
+        wrapped_share: WrappedKey,
+        encryption_key_id: Option<u32>,
+    ) -> Result<()> {
+        instructions::release_escrow_share(ctx, wrapped_share, encryption_key_id)
+    }
+
+    /// Access (view) a medical record; denials are logged, not reverted
     pub fn access_record(
         ctx: Context<AccessRecord>,
         client_info: Option<String>,
-    ) -> Result<()> {
+    ) -> Result<AccessOutcome> {
         instructions::access_record(ctx, client_info)
     }
 
+    /// Evaluate record access without side effects (dry run)
+    pub fn check_access(ctx: Context<CheckAccess>) -> Result<AccessCheck> {
+        instructions::check_access(ctx)
+    }
+
+    /// Check a retrieved blob's digest against the stored record hash
+    pub fn verify_record_integrity(
+        ctx: Context<VerifyRecordIntegrity>,
+        computed_hash: ContentHash,
+    ) -> Result<bool> {
+        instructions::verify_record_integrity(ctx, computed_hash)
+    }
+
     /// Emergency access to medical records (break-glass)
     pub fn emergency_access(
         ctx: Context<EmergencyAccess>,
@@ -87,18 +195,22 @@ pub mod cyphermed {
         requester_role: Role,
         reason: Option<String>,
         custom_expiration: Option<i64>,
+        encryption_key_id: Option<u32>,
     ) -> Result<()> {
-        instructions::request_access(ctx, requester_role, reason, custom_expiration)
+        instructions::request_access(ctx, requester_role, reason, custom_expiration, encryption_key_id)
     }
 
     /// Approve an access request
+    #[allow(clippy::too_many_arguments)]
     pub fn approve_access_request(
         ctx: Context<ApproveAccessRequest>,
-        allowed_record_types: Vec<RecordType>,
+        allowed_record_types: Vec<RecordTypeCode>,
         grant_expiration: Option<i64>,
         can_create: bool,
         can_modify: bool,
         can_view: bool,
+        can_delete: bool,
+        wrapped_key: Option<WrappedKey>,
     ) -> Result<()> {
         instructions::approve_access_request(
             ctx,
@@ -107,6 +219,8 @@ pub mod cyphermed {
             can_create,
             can_modify,
             can_view,
+            can_delete,
+            wrapped_key,
         )
     }
 
@@ -121,11 +235,36 @@ pub mod cyphermed {
     /// Update an existing medical record
     pub fn update_record(
         ctx: Context<UpdateRecord>,
-        new_data_hash: Option<String>,
+        new_data_hash: Option<ContentHash>,
+        new_storage: Option<StorageLocator>,
         new_metadata: Option<String>,
+        new_clinical_codes: Option<Vec<ClinicalCode>>,
         update_note: String,
     ) -> Result<()> {
-        instructions::update_record(ctx, new_data_hash, new_metadata, update_note)
+        instructions::update_record(
+            ctx,
+            new_data_hash,
+            new_storage,
+            new_metadata,
+            new_clinical_codes,
+            update_note,
+        )
+    }
+
+    /// Attach an additional blob (image, scanned page, etc.) to a record
+    pub fn add_attachment(
+        ctx: Context<AddAttachment>,
+        storage: StorageLocator,
+        data_hash: ContentHash,
+        mime_type: MimeType,
+        size_bytes: u64,
+    ) -> Result<()> {
+        instructions::add_attachment(ctx, storage, data_hash, mime_type, size_bytes)
+    }
+
+    /// Remove an attachment from a record
+    pub fn remove_attachment(ctx: Context<RemoveAttachment>) -> Result<()> {
+        instructions::remove_attachment(ctx)
     }
 
     /// Soft delete a medical record
@@ -136,16 +275,96 @@ pub mod cyphermed {
         instructions::delete_record(ctx, deletion_reason)
     }
 
+    /// Restore a soft-deleted medical record
+    pub fn restore_record(
+        ctx: Context<RestoreRecord>,
+        reason: RestoreReason,
+    ) -> Result<()> {
+        instructions::restore_record(ctx, reason)
+    }
+
+    /// Hard delete a medical record, leaving only a tombstone
+    pub fn purge_record<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurgeRecord<'info>>,
+        reason: ErasureReason,
+    ) -> Result<()> {
+        instructions::purge_record(ctx, reason)
+    }
+
+    /// Place a legal hold on a record
+    pub fn place_legal_hold(ctx: Context<PlaceLegalHold>, reason: String) -> Result<()> {
+        instructions::place_legal_hold(ctx, reason)
+    }
+
+    /// Lift a legal hold from a record
+    pub fn lift_legal_hold(ctx: Context<LiftLegalHold>) -> Result<()> {
+        instructions::lift_legal_hold(ctx)
+    }
+
+    /// Flag a record that is past its maximum retention (permissionless crank)
+    pub fn flag_retention_expired(ctx: Context<FlagRetentionExpired>) -> Result<()> {
+        instructions::flag_retention_expired(ctx)
+    }
+
+    /// Ask the authoring provider to correct a record
+    pub fn request_amendment(
+        ctx: Context<RequestAmendment>,
+        requested_change: String,
+    ) -> Result<()> {
+        instructions::request_amendment(ctx, requested_change)
+    }
+
+    /// Accept an amendment request, producing a new record version
+    pub fn accept_amendment(
+        ctx: Context<AcceptAmendment>,
+        new_data_hash: Option<ContentHash>,
+        new_storage: Option<StorageLocator>,
+        new_metadata: Option<String>,
+        new_clinical_codes: Option<Vec<ClinicalCode>>,
+        statement: Option<String>,
+    ) -> Result<()> {
+        instructions::accept_amendment(
+            ctx,
+            new_data_hash,
+            new_storage,
+            new_metadata,
+            new_clinical_codes,
+            statement,
+        )
+    }
+
+    /// Reject an amendment request with a statement
+    pub fn reject_amendment(ctx: Context<RejectAmendment>, statement: String) -> Result<()> {
+        instructions::reject_amendment(ctx, statement)
+    }
+
+    /// Attach a statement of disagreement to a rejected amendment
+    pub fn file_disagreement(ctx: Context<FileDisagreement>, statement: String) -> Result<()> {
+        instructions::file_disagreement(ctx, statement)
+    }
+
+    /// Apply a pending provider deletion (patient co-sign or after the window)
+    pub fn confirm_deletion(ctx: Context<ConfirmDeletion>) -> Result<()> {
+        instructions::confirm_deletion(ctx)
+    }
+
+    /// Reject a pending provider deletion
+    pub fn cancel_deletion(ctx: Context<CancelDeletion>) -> Result<()> {
+        instructions::cancel_deletion(ctx)
+    }
+
     /// Batch grant access to multiple providers
+    #[allow(clippy::too_many_arguments)]
     pub fn batch_grant_access(
         ctx: Context<BatchGrantAccess>,
         providers: Vec<Pubkey>,
         roles: Vec<Role>,
-        allowed_record_types: Vec<RecordType>,
+        allowed_record_types: Vec<RecordTypeCode>,
         expires_at: Option<i64>,
         can_create: bool,
         can_modify: bool,
         can_view: bool,
+        can_delete: bool,
         reason: Option<String>,
     ) -> Result<()> {
         instructions::batch_grant_access(
@@ -157,6 +376,7 @@ pub mod cyphermed {
             can_create,
             can_modify,
             can_view,
+            can_delete,
             reason,
         )
     }
@@ -169,6 +389,14 @@ pub mod cyphermed {
         instructions::update_patient(ctx, emergency_contact)
     }
 
+    /// Set how long provider deletions wait for the patient's co-signature
+    pub fn set_deletion_policy(
+        ctx: Context<SetDeletionPolicy>,
+        cosign_window: Option<i64>,
+    ) -> Result<()> {
+        instructions::set_deletion_policy(ctx, cosign_window)
+    }
+
     /// Deactivate patient account
     pub fn deactivate_patient(ctx: Context<DeactivatePatient>) -> Result<()> {
         instructions::deactivate_patient(ctx)
@@ -178,4 +406,113 @@ pub mod cyphermed {
     pub fn reactivate_patient(ctx: Context<ReactivatePatient>) -> Result<()> {
         instructions::reactivate_patient(ctx)
     }
+
+    /// Initialize the global program configuration
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        emergency_window_seconds: i64,
+        emergency_soft_threshold: u32,
+        emergency_hard_cap: u32,
+    ) -> Result<()> {
+        instructions::initialize_config(
+            ctx,
+            emergency_window_seconds,
+            emergency_soft_threshold,
+            emergency_hard_cap,
+        )
+    }
+
+    /// Update emergency access limits
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        emergency_window_seconds: i64,
+        emergency_soft_threshold: u32,
+        emergency_hard_cap: u32,
+    ) -> Result<()> {
+        instructions::update_config(
+            ctx,
+            emergency_window_seconds,
+            emergency_soft_threshold,
+            emergency_hard_cap,
+        )
+    }
+
+    /// Set the wallet allowed to place and lift legal holds
+    pub fn set_legal_authority(
+        ctx: Context<UpdateConfig>,
+        legal_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::set_legal_authority(ctx, legal_authority)
+    }
+
+    /// Set the minimum and maximum retention for a record type
+    pub fn set_retention_policy(
+        ctx: Context<UpdateConfig>,
+        record_type: RecordTypeCode,
+        min_retention_seconds: Option<i64>,
+        max_retention_seconds: Option<i64>,
+    ) -> Result<()> {
+        instructions::set_retention_policy(
+            ctx,
+            record_type,
+            min_retention_seconds,
+            max_retention_seconds,
+        )
+    }
+
+    /// Register or unregister a custom storage scheme
+    pub fn set_storage_scheme(
+        ctx: Context<UpdateConfig>,
+        scheme: String,
+        registered: bool,
+    ) -> Result<()> {
+        instructions::set_storage_scheme(ctx, scheme, registered)
+    }
+
+    /// Register or unregister an emergency responder
+    pub fn set_emergency_responder(
+        ctx: Context<SetEmergencyResponder>,
+        responder: Pubkey,
+        registered: bool,
+    ) -> Result<()> {
+        instructions::set_emergency_responder(ctx, responder, registered)
+    }
+
+    /// Register or update a record type in the governed registry
+    pub fn register_record_type(
+        ctx: Context<RegisterRecordType>,
+        code: RecordTypeCode,
+        name: String,
+        sensitivity: SensitivityTier,
+        default_min_retention_seconds: Option<i64>,
+        default_max_retention_seconds: Option<i64>,
+    ) -> Result<()> {
+        instructions::register_record_type(
+            ctx,
+            code,
+            name,
+            sensitivity,
+            default_min_retention_seconds,
+            default_max_retention_seconds,
+        )
+    }
+
+    /// Retire a record type so no new records use it
+    pub fn retire_record_type(ctx: Context<RetireRecordType>) -> Result<()> {
+        instructions::retire_record_type(ctx)
+    }
+
+    /// Initialize the patient's compressed audit tree
+    pub fn initialize_audit_tree(
+        ctx: Context<InitializeAuditTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        instructions::initialize_audit_tree(ctx, max_depth, max_buffer_size)
+    }
+
+    /// Switch between account and compressed audit storage
+    pub fn set_audit_mode(ctx: Context<SetAuditMode>, mode: AuditMode) -> Result<()> {
+        instructions::set_audit_mode(ctx, mode)
+    }
 }
@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use super::{Role, RecordType};
+use super::{Role, RecordTypeCode};
 
 /// Access Grant - defines who can access what
 #[account]
@@ -14,7 +14,7 @@ pub struct AccessGrant {
     pub role: Role,
     
     /// Types of records this grant allows access to
-    pub allowed_record_types: Vec<RecordType>,
+    pub allowed_record_types: Vec<RecordTypeCode>,
     
     /// Grant creation timestamp
     pub granted_at: i64,
@@ -34,9 +34,15 @@ pub struct AccessGrant {
     /// Can the provider view records?
     pub can_view: bool,
     
+    /// Can the provider delete records?
+    pub can_delete: bool,
+    
     /// Reason for access grant (optional)
     pub reason: Option<String>,
     
+    /// Provider encryption key in use for this grant (see `EncryptionKey`)
+    pub encryption_key_id: Option<u32>,
+    
     /// Who revoked this grant (if revoked)
     pub revoked_by: Option<Pubkey>,
     
@@ -48,19 +54,24 @@ pub struct AccessGrant {
 }
 
 impl AccessGrant {
+    /// Maximum number of record types one grant can cover
+    pub const MAX_RECORD_TYPES: usize = 16;
+
     /// Space required for AccessGrant account
     pub const LEN: usize = 8 + // discriminator
         32 + // patient
         32 + // provider
         (1 + 1) + // role (enum)
-        (4 + 5 * (1 + 1)) + // allowed_record_types (Vec with max 5 types)
+        (4 + Self::MAX_RECORD_TYPES * 2) + // allowed_record_types
         8 + // granted_at
         (1 + 8) + // expires_at
         1 + // is_active
         1 + // can_create
         1 + // can_modify
         1 + // can_view
+        1 + // can_delete
         (1 + 4 + 100) + // reason (Option<String> max 100 chars)
+        (1 + 4) + // encryption_key_id
         (1 + 32) + // revoked_by
         (1 + 8) + // revoked_at
         1; // bump
@@ -16,6 +16,9 @@ pub struct AccessRequest {
     /// Optional: Reason for access request (visible to patient if provided)
     pub reason: Option<String>,
     
+    /// Requester's encryption key the data key should be wrapped to (optional)
+    pub encryption_key_id: Option<u32>,
+    
     /// Request creation timestamp
     pub requested_at: i64,
     
@@ -48,6 +51,7 @@ impl AccessRequest {
         32 + // requester
         (1 + 1) + // requester_role (enum)
         (1 + 4 + 200) + // reason (Option<String> max 200 chars)
+        (1 + 4) + // encryption_key_id
         8 + // requested_at
         8 + // expires_at
         (1 + 1) + // status (enum)
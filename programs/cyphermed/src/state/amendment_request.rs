@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+
+/// Amendment Request - a patient's request to correct a provider-authored record
+#[account]
+pub struct AmendmentRequest {
+    /// Record the amendment applies to
+    pub record: Pubkey,
+    
+    /// Patient who filed the request
+    pub patient: Pubkey,
+    
+    /// Position among the record's amendment requests (PDA seed)
+    pub index: u32,
+    
+    /// Correction the patient is asking for
+    pub requested_change: String,
+    
+    /// Current status of the request
+    pub status: AmendmentStatus,
+    
+    /// Provider's statement (explanation of acceptance or grounds for rejection)
+    pub provider_statement: Option<String>,
+    
+    /// Patient's statement of disagreement with a rejection
+    pub disagreement_statement: Option<String>,
+    
+    /// Record version that incorporates an accepted amendment
+    pub resulting_version: Option<u32>,
+    
+    /// Request creation timestamp
+    pub requested_at: i64,
+    
+    /// When the provider responded
+    pub responded_at: Option<i64>,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl AmendmentRequest {
+    /// Space required for AmendmentRequest account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // record
+        32 + // patient
+        4 + // index
+        (4 + 500) + // requested_change (String max 500 chars)
+        1 + // status (enum)
+        (1 + 4 + 500) + // provider_statement (Option<String> max 500 chars)
+        (1 + 4 + 500) + // disagreement_statement (Option<String> max 500 chars)
+        (1 + 4) + // resulting_version
+        8 + // requested_at
+        (1 + 8) + // responded_at
+        1; // bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AmendmentStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
-use super::{Role, RecordType};
+use super::{Role, RecordTypeCode};
+use crate::errors::CypherMedError;
 
 /// Audit Log Entry - immutable record of all access events
 #[account]
@@ -7,8 +8,14 @@ pub struct AuditLog {
     /// Patient whose record was accessed
     pub patient: Pubkey,
     
-    /// The medical record that was accessed
-    pub record: Pubkey,
+    /// Position of this entry in the patient's audit sequence
+    pub sequence: u64,
+    
+    /// Hash of the patient's previous audit entry (all zeroes for the first)
+    pub prev_hash: [u8; 32],
+    
+    /// The medical record that was accessed (None for patient-level actions)
+    pub record: Option<Pubkey>,
     
     /// Who attempted/performed the access
     pub accessor: Pubkey,
@@ -19,8 +26,8 @@ pub struct AuditLog {
     /// Type of action performed
     pub action: AccessAction,
     
-    /// Type of record accessed
-    pub record_type: RecordType,
+    /// Type of record accessed (None for patient-level actions)
+    pub record_type: Option<RecordTypeCode>,
     
     /// Timestamp of the access
     pub timestamp: i64,
@@ -28,8 +35,8 @@ pub struct AuditLog {
     /// Was the access attempt successful?
     pub success: bool,
     
-    /// Reason for failure (if unsuccessful)
-    pub failure_reason: Option<String>,
+    /// Authorization decision (`Allowed` for successful actions)
+    pub decision: AccessDecision,
     
     /// Was this an emergency access?
     pub is_emergency: bool,
@@ -51,19 +58,27 @@ impl AuditLog {
     /// Space required for AuditLog account
     pub const LEN: usize = 8 + // discriminator
         32 + // patient
-        32 + // record
+        8 + // sequence
+        32 + // prev_hash
+        (1 + 32) + // record
         32 + // accessor
         (1 + 1) + // accessor_role
         (1 + 1) + // action (enum)
-        (1 + 1) + // record_type
+        (1 + 2) + // record_type
         8 + // timestamp
         1 + // success
-        (1 + 4 + 100) + // failure_reason
+        1 + // decision (enum)
         1 + // is_emergency
         (1 + 4 + 200) + // emergency_justification
         (1 + 4 + 50) + // client_info
         (1 + 4 + 100) + // metadata
         1; // bump
+
+    /// SHA-256 over the serialized entry, linking it to `prev_hash`
+    pub fn hash(&self) -> [u8; 32] {
+        let data = borsh::to_vec(self).unwrap();
+        solana_sha256_hasher::hash(&data).to_bytes()
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
@@ -75,4 +90,82 @@ pub enum AccessAction {
     GrantAccess,
     RevokeAccess,
     EmergencyAccess,
+    RequestAccess,
+    ApproveAccessRequest,
+    DenyAccessRequest,
+    BatchGrantAccess,
+    UpdatePatient,
+    DeactivatePatient,
+    ReactivatePatient,
+    RequestDeletion,
+    CancelDeletion,
+    Restore,
+    Purge,
+    RequestAmendment,
+    AcceptAmendment,
+    RejectAmendment,
+    FileDisagreement,
+    VerifyIntegrity,
+    AddAttachment,
+    RemoveAttachment,
+    IssueKeyEnvelope,
+    StartKeyRotation,
+    ConfigureKeyEscrow,
+    ReleaseEscrowShare,
+}
+
+/// Outcome of an authorization check, shared by every record instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AccessDecision {
+    Allowed,
+    PatientInactive,
+    RecordInactive,
+    NoAccessGrant,
+    GrantInactive,
+    GrantExpired,
+    NoViewPermission,
+    NoCreatePermission,
+    NoModifyPermission,
+    RecordTypeNotAllowed,
+    /// Denied by a fixed program rule rather than by the grant
+    PolicyDenied,
+    NoDeletePermission,
+    /// Patients correct provider-authored records through an amendment request
+    AmendmentRequired,
+}
+
+impl AccessDecision {
+    pub fn is_allowed(&self) -> bool {
+        *self == AccessDecision::Allowed
+    }
+
+    /// Fail with the error code matching this decision unless it is `Allowed`
+    pub fn require_allowed(self) -> Result<()> {
+        let error = match self {
+            AccessDecision::Allowed => return Ok(()),
+            AccessDecision::PatientInactive => CypherMedError::PatientInactive,
+            AccessDecision::RecordInactive => CypherMedError::RecordInactive,
+            AccessDecision::NoAccessGrant => CypherMedError::AccessGrantNotFound,
+            AccessDecision::GrantInactive => CypherMedError::AccessGrantRevoked,
+            AccessDecision::GrantExpired => CypherMedError::AccessGrantExpired,
+            AccessDecision::NoViewPermission => CypherMedError::NoViewPermission,
+            AccessDecision::NoCreatePermission => CypherMedError::NoCreatePermission,
+            AccessDecision::NoModifyPermission => CypherMedError::NoModifyPermission,
+            AccessDecision::RecordTypeNotAllowed => CypherMedError::RecordTypeNotAllowed,
+            AccessDecision::PolicyDenied => CypherMedError::Unauthorized,
+            AccessDecision::NoDeletePermission => CypherMedError::NoDeletePermission,
+            AccessDecision::AmendmentRequired => CypherMedError::AmendmentRequired,
+        };
+        msg!("Access decision: {:?}", self);
+        Err(error.into())
+    }
+}
+
+/// Per-patient choice of audit storage backend
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AuditMode {
+    /// One rent-paying `AuditLog` account per entry
+    Account,
+    /// Leaves appended to the patient's concurrent Merkle tree
+    Compressed,
 }
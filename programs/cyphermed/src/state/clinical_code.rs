@@ -0,0 +1,253 @@
+use anchor_lang::prelude::*;
+use crate::errors::CypherMedError;
+
+/// Terminology a clinical code is drawn from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CodeSystem {
+    /// ICD-10 diagnosis (`E11.9`, `S72.001A`)
+    Icd10,
+    /// LOINC observation or test, with check digit (`4548-4`)
+    Loinc,
+    /// SNOMED CT concept id (`73211009`)
+    Snomed,
+    /// RxNorm concept unique identifier (`860975`)
+    RxNorm,
+}
+
+/// A typed code describing a record's content, readable without decrypting it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ClinicalCode {
+    pub system: CodeSystem,
+    pub code: String,
+}
+
+impl ClinicalCode {
+    /// Maximum number of codes on one record
+    pub const MAX_CODES: usize = 8;
+
+    /// Longest code of any system (SNOMED CT ids run to 18 digits)
+    pub const MAX_CODE_LEN: usize = 18;
+
+    /// Space required for one code
+    pub const LEN: usize = 1 + // system
+        (4 + Self::MAX_CODE_LEN); // code
+
+    /// Space required for a record's code list
+    pub const LIST_LEN: usize = 4 + Self::MAX_CODES * Self::LEN;
+
+    /// Fail unless the code is well-formed for its system
+    pub fn validate(&self) -> Result<()> {
+        let code = self.code.as_bytes();
+        let valid = match self.system {
+            CodeSystem::Icd10 => {
+                // Letter, two characters, then an optional '.' and 1-4 more
+                let (category, subcategory) = match self.code.split_once('.') {
+                    Some((category, subcategory)) => (category.as_bytes(), Some(subcategory.as_bytes())),
+                    None => (code, None),
+                };
+                category.len() == 3
+                    && category[0].is_ascii_uppercase()
+                    && category[1].is_ascii_digit()
+                    && is_upper_alphanumeric(category[2])
+                    && match subcategory {
+                        Some(sub) => {
+                            (1..=4).contains(&sub.len())
+                                && sub.iter().copied().all(is_upper_alphanumeric)
+                        }
+                        None => true,
+                    }
+            }
+            CodeSystem::Loinc => match self.code.split_once('-') {
+                Some((number, check)) => {
+                    (1..=7).contains(&number.len())
+                        && number.bytes().all(|b| b.is_ascii_digit())
+                        && check.len() == 1
+                        && check.as_bytes()[0] == loinc_check_digit(number.as_bytes())
+                }
+                None => false,
+            },
+            CodeSystem::Snomed => {
+                (6..=Self::MAX_CODE_LEN).contains(&code.len())
+                    && code[0] != b'0'
+                    && code.iter().all(|b| b.is_ascii_digit())
+            }
+            CodeSystem::RxNorm => {
+                (1..=7).contains(&code.len())
+                    && code[0] != b'0'
+                    && code.iter().all(|b| b.is_ascii_digit())
+            }
+        };
+        require!(valid, CypherMedError::InvalidClinicalCode);
+        Ok(())
+    }
+
+    /// Fail unless `codes` fits on a record, every code is valid and none repeats
+    pub fn validate_list(codes: &[ClinicalCode]) -> Result<()> {
+        require!(codes.len() <= Self::MAX_CODES, CypherMedError::TooManyClinicalCodes);
+        for (i, code) in codes.iter().enumerate() {
+            code.validate()?;
+            require!(!codes[..i].contains(code), CypherMedError::DuplicateClinicalCode);
+        }
+        Ok(())
+    }
+}
+
+fn is_upper_alphanumeric(b: u8) -> bool {
+    b.is_ascii_uppercase() || b.is_ascii_digit()
+}
+
+/// LOINC's mod-10 (Luhn) check digit over the part number
+fn loinc_check_digit(number: &[u8]) -> u8 {
+    let sum: u32 = number
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let digit = (b - b'0') as u32;
+            if i % 2 == 0 {
+                let doubled = digit * 2;
+                doubled / 10 + doubled % 10
+            } else {
+                digit
+            }
+        })
+        .sum();
+    b'0' + ((10 - sum % 10) % 10) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(system: CodeSystem, code: &str) -> ClinicalCode {
+        ClinicalCode {
+            system,
+            code: code.to_string(),
+        }
+    }
+
+    fn check(system: CodeSystem, cases: &[(&str, bool)]) {
+        for &(value, valid) in cases {
+            assert_eq!(
+                code(system, value).validate().is_ok(),
+                valid,
+                "{:?} {:?}",
+                system,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn icd10_codes() {
+        check(
+            CodeSystem::Icd10,
+            &[
+                ("E11", true),
+                ("E11.9", true),
+                ("J11.1", true),
+                ("S72.001A", true),
+                ("C4A.0", true),
+                ("E1", false),
+                ("E111", false),
+                ("e11.9", false),
+                ("11.9", false),
+                ("EE1.9", false),
+                ("E11.", false),
+                ("E11.00001", false),
+                ("E11.9a", false),
+                ("E11-9", false),
+                ("", false),
+            ],
+        );
+    }
+
+    #[test]
+    fn loinc_codes_carry_a_mod10_check_digit() {
+        check(
+            CodeSystem::Loinc,
+            &[
+                ("4548-4", true),
+                ("4548-5", false),
+                ("2345-7", true),
+                ("2345-3", false),
+                ("718-7", true),
+                ("2160-0", true),
+                ("4548", false),
+                ("-4", false),
+                ("4548-", false),
+                ("4548-44", false),
+                ("12345678-9", false),
+                ("45A8-4", false),
+            ],
+        );
+    }
+
+    #[test]
+    fn snomed_ids_are_6_to_18_digits() {
+        check(
+            CodeSystem::Snomed,
+            &[
+                ("73211009", true),
+                ("123456", true),
+                ("12345", false),
+                ("123456789012345678", true),
+                ("1234567890123456789", false),
+                ("073211009", false),
+                ("7321100X", false),
+                ("", false),
+            ],
+        );
+    }
+
+    #[test]
+    fn rxnorm_cuis_are_up_to_7_digits() {
+        check(
+            CodeSystem::RxNorm,
+            &[
+                ("860975", true),
+                ("1", true),
+                ("1234567", true),
+                ("12345678", false),
+                ("0860975", false),
+                ("86O975", false),
+                ("", false),
+            ],
+        );
+    }
+
+    #[test]
+    fn lists_are_limited_to_max_codes() {
+        let codes: Vec<ClinicalCode> = (1..=ClinicalCode::MAX_CODES)
+            .map(|i| code(CodeSystem::RxNorm, &i.to_string()))
+            .collect();
+        assert!(ClinicalCode::validate_list(&codes).is_ok());
+        assert!(ClinicalCode::validate_list(&[]).is_ok());
+
+        let mut too_many = codes;
+        too_many.push(code(CodeSystem::RxNorm, "999"));
+        assert_eq!(
+            ClinicalCode::validate_list(&too_many).unwrap_err(),
+            error!(CypherMedError::TooManyClinicalCodes)
+        );
+    }
+
+    #[test]
+    fn lists_reject_invalid_and_duplicate_codes() {
+        let valid = code(CodeSystem::Icd10, "E11.9");
+        assert_eq!(
+            ClinicalCode::validate_list(&[valid.clone(), code(CodeSystem::Loinc, "4548-5")])
+                .unwrap_err(),
+            error!(CypherMedError::InvalidClinicalCode)
+        );
+        assert_eq!(
+            ClinicalCode::validate_list(&[valid.clone(), valid.clone()]).unwrap_err(),
+            error!(CypherMedError::DuplicateClinicalCode)
+        );
+
+        // The same code in another system is not a duplicate
+        let snomed = code(CodeSystem::Snomed, "860975");
+        let rxnorm = code(CodeSystem::RxNorm, "860975");
+        assert!(ClinicalCode::validate_list(&[snomed, rxnorm]).is_ok());
+    }
+}
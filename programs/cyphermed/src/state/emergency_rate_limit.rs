@@ -0,0 +1,191 @@
+use anchor_lang::prelude::*;
+
+/// Emergency Rate Limit - per-responder break-glass counters
+///
+/// Doubles as the responder registry: the config authority creates it when
+/// registering a responder, so a fresh keypair starts without break-glass
+/// rights instead of with a fresh quota.
+///
+/// Uses a sliding window counter: attempts in the previous fixed window are
+/// weighted by how much of it still overlaps the sliding window.
+#[account]
+pub struct EmergencyRateLimit {
+    /// Emergency responder these counters belong to
+    pub responder: Pubkey,
+    
+    /// Start of the current fixed window
+    pub window_start: i64,
+    
+    /// Attempts in the current fixed window
+    pub current_window_count: u32,
+    
+    /// Attempts in the fixed window before the current one
+    pub previous_window_count: u32,
+    
+    /// Lifetime number of emergency accesses by this responder
+    pub total_count: u64,
+    
+    /// When the responder last crossed the soft threshold
+    pub flagged_at: Option<i64>,
+    
+    /// Whether the config authority currently allows this responder to break glass
+    pub registered: bool,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl EmergencyRateLimit {
+    /// Space required for EmergencyRateLimit account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // responder
+        8 + // window_start
+        4 + // current_window_count
+        4 + // previous_window_count
+        8 + // total_count
+        (1 + 8) + // flagged_at
+        1 + // registered
+        1; // bump
+
+    /// Whether the responder crossed the soft threshold within the last window
+    pub fn is_flagged(&self, now: i64, window_seconds: i64) -> bool {
+        self.flagged_at.is_some_and(|at| now - at < window_seconds)
+    }
+
+    /// Move the fixed windows forward so that `now` falls in the current one
+    pub fn roll_window(&mut self, now: i64, window_seconds: i64) {
+        let start = now - now.rem_euclid(window_seconds);
+        if start == self.window_start {
+            return;
+        }
+
+        self.previous_window_count = if start == self.window_start + window_seconds {
+            self.current_window_count
+        } else {
+            0
+        };
+        self.current_window_count = 0;
+        self.window_start = start;
+    }
+
+    /// Estimated attempts within the sliding window ending at `now`
+    pub fn attempts_in_window(&self, now: i64, window_seconds: i64) -> u64 {
+        let elapsed = (now - self.window_start).clamp(0, window_seconds) as u64;
+        let window = window_seconds as u64;
+        let carried = (self.previous_window_count as u64) * (window - elapsed) / window;
+        carried + self.current_window_count as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: i64 = 3600;
+    const START: i64 = 1_700_000_000 - 1_700_000_000 % WINDOW;
+
+    fn counters() -> EmergencyRateLimit {
+        EmergencyRateLimit {
+            responder: Pubkey::new_from_array([1; 32]),
+            window_start: 0,
+            current_window_count: 0,
+            previous_window_count: 0,
+            total_count: 0,
+            flagged_at: None,
+            registered: true,
+            bump: 255,
+        }
+    }
+
+    /// Record an attempt the way `emergency_access` does
+    fn attempt(limit: &mut EmergencyRateLimit, now: i64) -> u64 {
+        limit.roll_window(now, WINDOW);
+        let attempts = limit.attempts_in_window(now, WINDOW);
+        limit.current_window_count += 1;
+        attempts
+    }
+
+    #[test]
+    fn roll_window_aligns_to_fixed_windows() {
+        let mut limit = counters();
+        limit.roll_window(START + 125, WINDOW);
+        assert_eq!(limit.window_start, START);
+
+        limit.current_window_count = 3;
+        limit.roll_window(START + WINDOW - 1, WINDOW);
+        assert_eq!(limit.window_start, START);
+        assert_eq!(limit.current_window_count, 3);
+    }
+
+    #[test]
+    fn roll_window_carries_only_the_adjacent_window() {
+        let mut limit = counters();
+        limit.roll_window(START, WINDOW);
+        limit.current_window_count = 4;
+
+        limit.roll_window(START + WINDOW + 10, WINDOW);
+        assert_eq!(limit.window_start, START + WINDOW);
+        assert_eq!(limit.previous_window_count, 4);
+        assert_eq!(limit.current_window_count, 0);
+
+        limit.current_window_count = 2;
+        limit.roll_window(START + 3 * WINDOW, WINDOW);
+        assert_eq!(limit.window_start, START + 3 * WINDOW);
+        assert_eq!(limit.previous_window_count, 0);
+        assert_eq!(limit.current_window_count, 0);
+    }
+
+    #[test]
+    fn attempts_decay_across_the_sliding_window() {
+        let mut limit = counters();
+        limit.roll_window(START, WINDOW);
+        limit.current_window_count = 10;
+        assert_eq!(limit.attempts_in_window(START + 10, WINDOW), 10);
+
+        limit.roll_window(START + WINDOW, WINDOW);
+        assert_eq!(limit.attempts_in_window(START + WINDOW, WINDOW), 10);
+        assert_eq!(limit.attempts_in_window(START + WINDOW + WINDOW / 4, WINDOW), 7);
+        assert_eq!(limit.attempts_in_window(START + WINDOW + WINDOW / 2, WINDOW), 5);
+
+        limit.current_window_count = 1;
+        assert_eq!(limit.attempts_in_window(START + WINDOW + WINDOW / 2, WINDOW), 6);
+    }
+
+    #[test]
+    fn soft_threshold_is_reached_once_per_burst() {
+        let soft_threshold = 3;
+        let mut limit = counters();
+        let reached: Vec<u64> = (0..5)
+            .map(|i| attempt(&mut limit, START + i * 60))
+            .filter(|attempts| attempts + 1 == soft_threshold)
+            .collect();
+        assert_eq!(reached, vec![soft_threshold - 1]);
+    }
+
+    #[test]
+    fn hard_limit_holds_across_a_window_boundary() {
+        let hard_cap = 5;
+        let mut limit = counters();
+        for i in 0..hard_cap {
+            assert!(attempt(&mut limit, START + WINDOW - 100 + i as i64) < hard_cap);
+        }
+
+        // Crossing the boundary does not reset the count
+        limit.roll_window(START + WINDOW, WINDOW);
+        assert_eq!(limit.attempts_in_window(START + WINDOW, WINDOW), hard_cap);
+
+        // A full window later the quota is back
+        limit.roll_window(START + 2 * WINDOW, WINDOW);
+        assert!(limit.attempts_in_window(START + 2 * WINDOW, WINDOW) < hard_cap);
+    }
+
+    #[test]
+    fn flag_expires_after_a_window() {
+        let mut limit = counters();
+        assert!(!limit.is_flagged(START, WINDOW));
+
+        limit.flagged_at = Some(START);
+        assert!(limit.is_flagged(START + WINDOW - 1, WINDOW));
+        assert!(!limit.is_flagged(START + WINDOW, WINDOW));
+    }
+}
@@ -0,0 +1,189 @@
+use anchor_lang::prelude::*;
+use crate::errors::CypherMedError;
+
+/// Encryption Key - a wallet's X25519 public keys for receiving wrapped data keys
+///
+/// Solana wallets sign with Ed25519 and cannot be used for key agreement, so
+/// each wallet publishes separate X25519 keys here. Every key is bound to the
+/// wallet by an Ed25519 signature over `encryption_key_binding_message`.
+#[account]
+pub struct EncryptionKey {
+    /// Wallet that registered the keys
+    pub owner: Pubkey,
+    
+    /// Registered keys, oldest first (at most one is active)
+    pub keys: Vec<X25519Key>,
+    
+    /// Id the next registered key will receive
+    pub next_key_id: u32,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl EncryptionKey {
+    /// Maximum number of keys kept per wallet (only revoked keys no envelope
+    /// is wrapped to are dropped to make room)
+    pub const MAX_KEYS: usize = 4;
+
+    /// Space required for EncryptionKey account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // owner
+        (4 + Self::MAX_KEYS * X25519Key::LEN) + // keys
+        4 + // next_key_id
+        1; // bump
+
+    /// The key new envelopes should be wrapped to
+    pub fn active_key(&self) -> Option<&X25519Key> {
+        self.keys.iter().find(|key| key.status == KeyStatus::Active)
+    }
+
+    /// Key `key_id`, if the wallet still holds it
+    pub fn key(&self, key_id: u32) -> Option<&X25519Key> {
+        self.keys.iter().find(|key| key.key_id == key_id)
+    }
+
+    /// The referenced key (or the active one when `key_id` is None); it must
+    /// be active to receive new envelopes
+    pub fn usable_key(&self, key_id: Option<u32>) -> Result<&X25519Key> {
+        let key = match key_id {
+            Some(key_id) => self.key(key_id),
+            None => self.active_key(),
+        }
+        .ok_or(CypherMedError::EncryptionKeyNotFound)?;
+        require!(key.status == KeyStatus::Active, CypherMedError::EncryptionKeyNotActive);
+        Ok(key)
+    }
+
+    /// Ensure a new key fits, dropping the oldest revoked key without
+    /// envelopes if the list is full; active and rotated keys are never dropped
+    pub fn make_room(&mut self) -> Result<()> {
+        if self.keys.len() < Self::MAX_KEYS {
+            return Ok(());
+        }
+        let index = self
+            .keys
+            .iter()
+            .position(|key| key.status == KeyStatus::Revoked && key.envelope_count == 0)
+            .ok_or(CypherMedError::EncryptionKeyLimitReached)?;
+        self.keys.remove(index);
+        Ok(())
+    }
+
+    /// Count an envelope newly wrapped to key `key_id`
+    pub fn add_envelope(&mut self, key_id: u32) -> Result<()> {
+        let key = self
+            .keys
+            .iter_mut()
+            .find(|key| key.key_id == key_id)
+            .ok_or(CypherMedError::EncryptionKeyNotFound)?;
+        key.envelope_count = key.envelope_count.checked_add(1).unwrap();
+        Ok(())
+    }
+
+    /// Stop counting an envelope wrapped to key `key_id` (closed or re-issued)
+    pub fn release_envelope(&mut self, key_id: u32) {
+        if let Some(key) = self.keys.iter_mut().find(|key| key.key_id == key_id) {
+            key.envelope_count = key.envelope_count.saturating_sub(1);
+        }
+    }
+}
+
+/// One X25519 public key and the wallet's signature binding it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct X25519Key {
+    pub key_id: u32,
+    pub public_key: [u8; 32],
+    /// Owner's Ed25519 signature over the binding message
+    pub binding_signature: [u8; 64],
+    pub created_at: i64,
+    pub status: KeyStatus,
+    /// When the key was rotated out or revoked
+    pub status_changed_at: Option<i64>,
+    /// Key envelopes currently wrapped to this key
+    pub envelope_count: u32,
+}
+
+impl X25519Key {
+    pub const LEN: usize = 4 + // key_id
+        32 + // public_key
+        64 + // binding_signature
+        8 + // created_at
+        1 + // status (enum)
+        (1 + 8) + // status_changed_at
+        4; // envelope_count
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyStatus {
+    /// Current key; new envelopes are wrapped to it
+    Active,
+    /// Superseded by a newer key; existing envelopes still open with it
+    Rotated,
+    /// Compromised or withdrawn; must not receive envelopes, and the ones
+    /// already wrapped to it are closed
+    Revoked,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(statuses: &[KeyStatus]) -> EncryptionKey {
+        EncryptionKey {
+            owner: Pubkey::new_from_array([1; 32]),
+            keys: statuses
+                .iter()
+                .enumerate()
+                .map(|(i, &status)| X25519Key {
+                    key_id: i as u32,
+                    public_key: [i as u8; 32],
+                    binding_signature: [0; 64],
+                    created_at: 0,
+                    status,
+                    status_changed_at: None,
+                    envelope_count: 0,
+                })
+                .collect(),
+            next_key_id: statuses.len() as u32,
+            bump: 255,
+        }
+    }
+
+    fn key_ids(registration: &EncryptionKey) -> Vec<u32> {
+        registration.keys.iter().map(|key| key.key_id).collect()
+    }
+
+    #[test]
+    fn full_list_drops_the_oldest_revoked_key_without_envelopes() {
+        use KeyStatus::*;
+        let mut registration = registration(&[Rotated, Revoked, Revoked, Active]);
+        registration.add_envelope(1).unwrap();
+
+        registration.make_room().unwrap();
+        assert_eq!(key_ids(&registration), vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn full_list_without_a_droppable_key_is_rejected() {
+        use KeyStatus::*;
+        let mut registration = registration(&[Rotated, Rotated, Revoked, Active]);
+        registration.add_envelope(2).unwrap();
+        assert_eq!(
+            registration.make_room().unwrap_err(),
+            error!(CypherMedError::EncryptionKeyLimitReached)
+        );
+
+        // Once its last envelope is closed the revoked key can go
+        registration.release_envelope(2);
+        registration.make_room().unwrap();
+        assert_eq!(key_ids(&registration), vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn list_with_room_is_left_alone() {
+        let mut registration = registration(&[KeyStatus::Revoked, KeyStatus::Active]);
+        registration.make_room().unwrap();
+        assert_eq!(key_ids(&registration), vec![0, 1]);
+    }
+}
@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+
+/// Key Envelope - the patient's data key wrapped to one grantee's X25519 key
+#[account]
+pub struct KeyEnvelope {
+    /// Patient whose data key is wrapped
+    pub patient: Pubkey,
+    
+    /// Grantee who can unwrap the data key
+    pub grantee: Pubkey,
+    
+    /// Id of the grantee's encryption key the data key was wrapped to
+    pub recipient_key_id: u32,
+    
+    /// X25519 public key the data key was wrapped to
+    pub recipient_key: [u8; 32],
+    
+    /// Data-key epoch of the wrapped key
+    pub epoch: u32,
+    
+    /// The wrapped data key
+    pub wrapped_key: WrappedKey,
+    
+    /// Envelope creation timestamp
+    pub created_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl KeyEnvelope {
+    /// Space required for KeyEnvelope account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // patient
+        32 + // grantee
+        4 + // recipient_key_id
+        32 + // recipient_key
+        4 + // epoch
+        WrappedKey::LEN + // wrapped_key
+        8 + // created_at
+        1; // bump
+}
+
+/// A 32-byte data key sealed with X25519 + HKDF-SHA256 + ChaCha20-Poly1305
+///
+/// Wrapping happens client-side; the program only stores the result.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WrappedKey {
+    /// Sender's one-time X25519 public key
+    pub ephemeral_public_key: [u8; 32],
+    /// AEAD nonce
+    pub nonce: [u8; 12],
+    /// Encrypted data key followed by the 16-byte authentication tag
+    pub ciphertext: [u8; 48],
+}
+
+impl WrappedKey {
+    pub const LEN: usize = 32 + // ephemeral_public_key
+        12 + // nonce
+        48; // ciphertext
+}
@@ -0,0 +1,157 @@
+use anchor_lang::prelude::*;
+use crate::errors::CypherMedError;
+use crate::state::WrappedKey;
+
+/// Key Escrow - the patient's emergency decryption key split into Shamir
+/// shares held by custodians
+///
+/// Splitting and wrapping happen client-side; the program stores each
+/// custodian's wrapped share with a commitment to it. A completed
+/// `emergency_access` opens a release window in which custodians re-wrap
+/// their share to the responder's registered encryption key.
+#[account]
+pub struct KeyEscrow {
+    /// Patient whose key is escrowed
+    pub patient: Pubkey,
+    
+    /// Shares needed to recover the key
+    pub threshold: u8,
+    
+    /// Data-key epoch of the escrowed key
+    pub key_epoch: u32,
+    
+    /// Share holders
+    pub custodians: Vec<EscrowCustodian>,
+    
+    /// Emergency responder the current release window is for
+    pub release_to: Option<Pubkey>,
+    
+    /// When the current release window opened
+    pub release_opened_at: Option<i64>,
+    
+    /// Escrow configuration timestamp
+    pub configured_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl KeyEscrow {
+    /// Maximum number of custodians
+    pub const MAX_CUSTODIANS: usize = 7;
+
+    /// How long custodians may release shares after an emergency access
+    pub const RELEASE_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+
+    /// Space required for KeyEscrow account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // patient
+        1 + // threshold
+        4 + // key_epoch
+        (4 + Self::MAX_CUSTODIANS * EscrowCustodian::LEN) + // custodians
+        (1 + 32) + // release_to
+        (1 + 8) + // release_opened_at
+        8 + // configured_at
+        1; // bump
+
+    /// Responder allowed to receive shares at `now`, if a window is open
+    pub fn release_recipient(&self, now: i64) -> Option<Pubkey> {
+        let opened_at = self.release_opened_at?;
+        if now > opened_at.saturating_add(Self::RELEASE_WINDOW_SECONDS) {
+            return None;
+        }
+        self.release_to
+    }
+
+    /// Open a release window for `responder`
+    ///
+    /// A window still open for another responder cannot be taken over, and
+    /// one already open for `responder` keeps the shares released so far.
+    /// Only once a window has lapsed are its releases discarded.
+    pub fn open_release(&mut self, responder: Pubkey, data_key_epoch: u32, now: i64) -> Result<()> {
+        require!(self.key_epoch == data_key_epoch, CypherMedError::EscrowKeyOutdated);
+        match self.release_recipient(now) {
+            Some(current) if current == responder => return Ok(()),
+            Some(_) => return err!(CypherMedError::EscrowReleaseInProgress),
+            None => {}
+        }
+
+        self.release_to = Some(responder);
+        self.release_opened_at = Some(now);
+        for custodian in self.custodians.iter_mut() {
+            custodian.released = None;
+        }
+        Ok(())
+    }
+
+    pub fn custodian_mut(&mut self, custodian: &Pubkey) -> Result<&mut EscrowCustodian> {
+        self.custodians
+            .iter_mut()
+            .find(|entry| entry.custodian == *custodian)
+            .ok_or_else(|| error!(CypherMedError::NotEscrowCustodian))
+    }
+
+    pub fn released_count(&self) -> usize {
+        self.custodians.iter().filter(|entry| entry.released.is_some()).count()
+    }
+}
+
+/// One custodian's share of the escrowed key
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct EscrowCustodian {
+    /// Wallet allowed to release the share
+    pub custodian: Pubkey,
+    
+    /// X25519 key the share is wrapped to (emergency contact or HSM key)
+    pub recipient_key: [u8; 32],
+    
+    /// Shamir x-coordinate of the share (never 0)
+    pub share_index: u8,
+    
+    /// SHA-256 commitment to the share, checked when recombining
+    pub commitment: [u8; 32],
+    
+    /// The share wrapped to `recipient_key`
+    pub wrapped_share: WrappedKey,
+    
+    /// The share re-wrapped to the responder during the open window
+    pub released: Option<ReleasedShare>,
+}
+
+impl EscrowCustodian {
+    pub const LEN: usize = 32 + // custodian
+        32 + // recipient_key
+        1 + // share_index
+        32 + // commitment
+        WrappedKey::LEN + // wrapped_share
+        (1 + ReleasedShare::LEN); // released
+}
+
+/// A share handed to an emergency responder
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ReleasedShare {
+    /// Id of the responder's encryption key the share was wrapped to
+    pub recipient_key_id: u32,
+    
+    /// The share wrapped to the responder's key
+    pub wrapped_share: WrappedKey,
+    
+    /// Release timestamp
+    pub released_at: i64,
+}
+
+impl ReleasedShare {
+    pub const LEN: usize = 4 + // recipient_key_id
+        WrappedKey::LEN + // wrapped_share
+        8; // released_at
+}
+
+/// A custodian's share as supplied when configuring the escrow
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EscrowShareInput {
+    pub custodian: Pubkey,
+    pub recipient_key: [u8; 32],
+    pub share_index: u8,
+    pub commitment: [u8; 32],
+    pub wrapped_share: WrappedKey,
+}
@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+use crate::errors::CypherMedError;
+
+/// Key Rotation - progress of re-encrypting a patient's records under a new data key
+///
+/// One account per patient, reused by every rotation. Records marked for
+/// re-key stay in `pending_records` until their content is re-encrypted under
+/// `new_epoch` through `update_record` (or an accepted amendment), or until
+/// they are deleted. The rotation ends only when the patient completes or
+/// cancels it.
+#[account]
+pub struct KeyRotation {
+    /// Patient whose data key is being rotated
+    pub patient: Pubkey,
+    
+    /// Data-key epoch that is no longer trusted
+    pub compromised_epoch: u32,
+    
+    /// Epoch records are being re-encrypted under
+    pub new_epoch: u32,
+    
+    /// Grantee whose revocation prompted the rotation (if any)
+    pub revoked_grantee: Option<Pubkey>,
+    
+    /// Rotation start timestamp
+    pub started_at: i64,
+    
+    /// When the patient completed or cancelled the rotation (None while in progress)
+    pub completed_at: Option<i64>,
+    
+    /// Records marked for re-key during this rotation
+    pub records_marked: u32,
+    
+    /// Marked records already re-encrypted under `new_epoch`
+    pub records_rekeyed: u32,
+    
+    /// Marked records deleted or purged before being re-keyed
+    pub records_dropped: u32,
+    
+    /// Records still encrypted under a compromised epoch
+    pub pending_records: Vec<Pubkey>,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl KeyRotation {
+    /// Maximum number of records awaiting re-key at once
+    pub const MAX_PENDING_RECORDS: usize = 64;
+
+    /// Space required for KeyRotation account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // patient
+        4 + // compromised_epoch
+        4 + // new_epoch
+        (1 + 32) + // revoked_grantee
+        8 + // started_at
+        (1 + 8) + // completed_at
+        4 + // records_marked
+        4 + // records_rekeyed
+        4 + // records_dropped
+        (4 + Self::MAX_PENDING_RECORDS * 32) + // pending_records
+        1; // bump
+
+    pub fn in_progress(&self) -> bool {
+        self.started_at != 0 && self.completed_at.is_none()
+    }
+
+    /// Add `record` to the records awaiting re-key
+    pub fn mark_pending(&mut self, record: Pubkey) -> Result<()> {
+        require!(
+            self.pending_records.len() < Self::MAX_PENDING_RECORDS,
+            CypherMedError::TooManyPendingRekeys
+        );
+        self.pending_records.push(record);
+        self.records_marked = self.records_marked.checked_add(1).unwrap();
+        Ok(())
+    }
+
+    /// Take a re-encrypted `record` off the pending list
+    pub fn mark_rekeyed(&mut self, record: &Pubkey) {
+        if self.remove_pending(record) {
+            self.records_rekeyed = self.records_rekeyed.checked_add(1).unwrap();
+        }
+    }
+
+    /// Take a deleted `record` off the pending list
+    pub fn drop_pending(&mut self, record: &Pubkey) {
+        if self.remove_pending(record) {
+            self.records_dropped = self.records_dropped.checked_add(1).unwrap();
+        }
+    }
+
+    fn remove_pending(&mut self, record: &Pubkey) -> bool {
+        let pending = self.pending_records.len();
+        self.pending_records.retain(|pending| pending != record);
+        self.pending_records.len() < pending
+    }
+}
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+/// Legal Hold - freezes deletion of a record while litigation is pending
+#[account]
+pub struct LegalHold {
+    /// Record under hold
+    pub record: Pubkey,
+    
+    /// Patient who owns the record
+    pub patient: Pubkey,
+    
+    /// Legal authority that placed the hold
+    pub placed_by: Pubkey,
+    
+    /// Reason for the hold (case reference, etc.)
+    pub reason: String,
+    
+    /// Hold placement timestamp
+    pub placed_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl LegalHold {
+    /// Space required for LegalHold account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // record
+        32 + // patient
+        32 + // placed_by
+        (4 + 200) + // reason (String max 200 chars)
+        8 + // placed_at
+        1; // bump
+}
@@ -1,11 +1,43 @@
 pub mod patient;
 pub mod record;
+pub mod record_version;
+pub mod record_attachment;
+pub mod storage_locator;
+pub mod clinical_code;
+pub mod record_type_definition;
 pub mod access_grant;
 pub mod audit_log;
 pub mod access_request;
+pub mod program_config;
+pub mod emergency_rate_limit;
+pub mod pending_deletion;
+pub mod record_tombstone;
+pub mod legal_hold;
+pub mod amendment_request;
+pub mod provider_signing_key;
+pub mod encryption_key;
+pub mod key_envelope;
+pub mod key_rotation;
+pub mod key_escrow;
 
 pub use patient::*;
 pub use record::*;
+pub use record_version::*;
+pub use record_attachment::*;
+pub use storage_locator::*;
+pub use clinical_code::*;
+pub use record_type_definition::*;
 pub use access_grant::*;
 pub use audit_log::*;
 pub use access_request::*;
+pub use program_config::*;
+pub use emergency_rate_limit::*;
+pub use pending_deletion::*;
+pub use record_tombstone::*;
+pub use legal_hold::*;
+pub use amendment_request::*;
+pub use provider_signing_key::*;
+pub use encryption_key::*;
+pub use key_envelope::*;
+pub use key_rotation::*;
+pub use key_escrow::*;
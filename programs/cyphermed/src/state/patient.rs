@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use super::{AuditLog, AuditMode};
+use crate::errors::CypherMedError;
 
 /// Patient account - represents a patient in the system
 #[account]
@@ -30,6 +32,25 @@ pub struct Patient {
     /// Emergency contact wallet (optional)
     pub emergency_contact: Option<Pubkey>,
     
+    /// Number of audit entries written (next audit sequence number)
+    pub audit_count: u64,
+    
+    /// Hash of the most recent audit entry (all zeroes before the first)
+    pub audit_head_hash: [u8; 32],
+    
+    /// Where new audit entries are written
+    pub audit_mode: AuditMode,
+    
+    /// Concurrent Merkle tree for compressed audit entries (if initialized)
+    pub audit_tree: Option<Pubkey>,
+    
+    /// Seconds a provider deletion waits for the patient's co-signature
+    /// before it takes effect (None = provider deletions apply immediately)
+    pub deletion_cosign_window: Option<i64>,
+    
+    /// Current data-key epoch (bumped by every key rotation)
+    pub data_key_epoch: u32,
+    
     /// Bump seed for PDA
     pub bump: u8,
 }
@@ -46,7 +67,26 @@ impl Patient {
         8 + // access_grant_count
         1 + // is_active
         (1 + 32) + // emergency_contact (Option<Pubkey>)
+        8 + // audit_count
+        32 + // audit_head_hash
+        1 + // audit_mode (enum)
+        (1 + 32) + // audit_tree
+        (1 + 8) + // deletion_cosign_window
+        4 + // data_key_epoch
         1; // bump
+
+    /// Link a fully populated audit entry into this patient's hash chain
+    pub fn append_audit(&mut self, entry: &mut AuditLog) -> Result<()> {
+        let next_count = self
+            .audit_count
+            .checked_add(1)
+            .ok_or(CypherMedError::AuditSequenceOverflow)?;
+        entry.sequence = self.audit_count;
+        entry.prev_hash = self.audit_head_hash;
+        self.audit_head_hash = entry.hash();
+        self.audit_count = next_count;
+        Ok(())
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
@@ -56,8 +96,11 @@ pub enum Role {
     Hospital,
     Insurer,
     EmergencyResponder,
+    /// Holder of a key escrow share without a grant of their own
+    EscrowCustodian,
 }
 
+/// Built-in record types; records and grants store their `code()`
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum RecordType {
     GeneralMedical,
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+use super::Role;
+
+/// Pending Deletion - a provider deletion awaiting the patient's co-signature
+#[account]
+pub struct PendingDeletion {
+    /// Record to be deleted
+    pub record: Pubkey,
+    
+    /// Patient who owns the record
+    pub patient: Pubkey,
+    
+    /// Provider who requested the deletion
+    pub requested_by: Pubkey,
+    
+    /// Role the requester acted under
+    pub requester_role: Role,
+    
+    /// Reason given for the deletion
+    pub reason: String,
+    
+    /// Request timestamp
+    pub requested_at: i64,
+    
+    /// When the deletion takes effect without the patient's co-signature
+    pub executes_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl PendingDeletion {
+    /// Space required for PendingDeletion account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // record
+        32 + // patient
+        32 + // requested_by
+        1 + // requester_role (enum)
+        (4 + 300) + // reason (String max 300 chars)
+        8 + // requested_at
+        8 + // executes_at
+        1; // bump
+}
@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use super::{RecordTypeCode, RecordTypeDefinition, StorageLocator};
+
+/// Program Config - global settings shared by every patient
+#[account]
+pub struct ProgramConfig {
+    /// Wallet allowed to update the configuration
+    pub authority: Pubkey,
+    
+    /// Length of the emergency access rate-limit window in seconds
+    pub emergency_window_seconds: i64,
+    
+    /// Break-glass attempts per window after which a co-signer is required
+    pub emergency_soft_threshold: u32,
+    
+    /// Break-glass attempts per window that can never be exceeded
+    pub emergency_hard_cap: u32,
+    
+    /// Wallet allowed to place and lift legal holds (None = holds disabled)
+    pub legal_authority: Option<Pubkey>,
+    
+    /// Retention rules per record type (at most one per type)
+    pub retention_policies: Vec<RetentionPolicy>,
+    
+    /// Names of custom storage schemes records may use (e.g. "pacs")
+    pub storage_schemes: Vec<String>,
+    
+    /// Last updated timestamp
+    pub updated_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ProgramConfig {
+    /// Space required for ProgramConfig account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // authority
+        8 + // emergency_window_seconds
+        4 + // emergency_soft_threshold
+        4 + // emergency_hard_cap
+        (1 + 32) + // legal_authority
+        (4 + Self::MAX_RETENTION_POLICIES * RetentionPolicy::LEN) + // retention_policies
+        (4 + Self::MAX_STORAGE_SCHEMES * (4 + StorageLocator::MAX_SCHEME_LEN)) + // storage_schemes
+        8 + // updated_at
+        1; // bump
+
+    /// Maximum number of custom storage schemes
+    pub const MAX_STORAGE_SCHEMES: usize = 8;
+
+    /// Maximum number of record types with configured retention rules
+    pub const MAX_RETENTION_POLICIES: usize = 16;
+
+    /// Retention rules for a record type, if any are configured
+    pub fn retention_policy(&self, record_type: RecordTypeCode) -> Option<&RetentionPolicy> {
+        self.retention_policies
+            .iter()
+            .find(|policy| policy.record_type == record_type)
+    }
+
+    /// Configured retention rules for a record type, falling back on the
+    /// defaults of its definition
+    pub fn effective_retention(
+        &self,
+        record_type: RecordTypeCode,
+        definition: Option<&RecordTypeDefinition>,
+    ) -> Option<RetentionPolicy> {
+        self.retention_policy(record_type)
+            .copied()
+            .or_else(|| definition.map(RecordTypeDefinition::default_retention))
+    }
+}
+
+/// Minimum and maximum retention for one record type
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RetentionPolicy {
+    pub record_type: RecordTypeCode,
+    /// Seconds after creation before a record may be deleted
+    pub min_retention_seconds: Option<i64>,
+    /// Seconds after creation after which a record should be erased
+    pub max_retention_seconds: Option<i64>,
+}
+
+impl RetentionPolicy {
+    pub const LEN: usize = 2 + // record_type (code)
+        (1 + 8) + // min_retention_seconds
+        (1 + 8); // max_retention_seconds
+}
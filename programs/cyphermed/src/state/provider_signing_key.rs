@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+use crate::errors::CypherMedError;
+
+/// Provider Signing Key - Ed25519 key a provider attests record content with
+#[account]
+pub struct ProviderSigningKey {
+    /// Provider wallet that registered the key
+    pub provider: Pubkey,
+    
+    /// Ed25519 public key whose signatures count as the provider's attestation
+    pub signing_key: Pubkey,
+    
+    /// Registration (or last rotation) timestamp
+    pub registered_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ProviderSigningKey {
+    /// Space required for ProviderSigningKey account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // provider
+        32 + // signing_key
+        8 + // registered_at
+        1; // bump
+
+    /// Read the registration at its PDA, if the provider has registered a key
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*info.owner, crate::ID, CypherMedError::InvalidAttestation);
+        let data = info.try_borrow_data()?;
+        Ok(Some(Self::try_deserialize(&mut &data[..])?))
+    }
+}
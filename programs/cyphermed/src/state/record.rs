@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use super::RecordType;
+use super::{ClinicalCode, RecordTypeCode, Role, StorageLocator};
 
 /// Medical Record Metadata - stored on-chain
 #[account]
@@ -10,17 +10,20 @@ pub struct MedicalRecord {
     /// Healthcare provider who created the record
     pub created_by: Pubkey,
     
+    /// Role the creator acted under when the record was created
+    pub created_by_role: Role,
+    
     /// Type of medical record
-    pub record_type: RecordType,
+    pub record_type: RecordTypeCode,
     
     /// Unique record ID (can be used to fetch from off-chain DB)
     pub record_id: String,
     
-    /// Hash of the encrypted off-chain data (for integrity verification)
-    pub data_hash: String,
+    /// Digest of the encrypted off-chain data (for integrity verification)
+    pub data_hash: ContentHash,
     
-    /// IPFS/Arweave CID for distributed storage (optional)
-    pub storage_cid: Option<String>,
+    /// Where the encrypted off-chain data is stored (optional)
+    pub storage: Option<StorageLocator>,
     
     /// Creation timestamp
     pub created_at: i64,
@@ -28,6 +31,10 @@ pub struct MedicalRecord {
     /// Last modified timestamp
     pub modified_at: i64,
     
+    /// When the current version's content became current (unlike
+    /// `modified_at`, not moved by attachments, deletion or restoration)
+    pub content_updated_at: i64,
+    
     /// Last accessed timestamp
     pub last_accessed: i64,
     
@@ -37,9 +44,45 @@ pub struct MedicalRecord {
     /// Is this record active or archived?
     pub is_active: bool,
     
+    /// Who soft-deleted the record (None while active)
+    pub deleted_by: Option<Pubkey>,
+    
+    /// When the record was soft-deleted (None while active)
+    pub deleted_at: Option<i64>,
+    
+    /// When a crank found the record past its maximum retention (if ever)
+    pub retention_flagged_at: Option<i64>,
+    
     /// Additional metadata (optional)
     pub metadata: Option<String>,
     
+    /// Current content version (starts at 1, bumped on every update)
+    pub version: u32,
+    
+    /// Number of amendment requests filed (next amendment index)
+    pub amendment_count: u32,
+    
+    /// Whether the current version carries a verified provider signature
+    pub attested: bool,
+    
+    /// Ed25519 key that attested the current version (None if unattested)
+    pub attested_by: Option<Pubkey>,
+    
+    /// Data-key epoch the current content is encrypted under
+    pub key_epoch: u32,
+    
+    /// Marked by the patient for re-encryption after a key rotation
+    pub needs_rekey: bool,
+    
+    /// Number of attachments currently on the record
+    pub attachment_count: u32,
+    
+    /// Index the next attachment will be stored under
+    pub next_attachment_index: u32,
+    
+    /// Diagnosis, test and medication codes describing the current content
+    pub clinical_codes: Vec<ClinicalCode>,
+    
     /// Bump seed for PDA
     pub bump: u8,
 }
@@ -49,15 +92,72 @@ impl MedicalRecord {
     pub const LEN: usize = 8 + // discriminator
         32 + // patient
         32 + // created_by
-        (1 + 1) + // record_type (enum)
+        1 + // created_by_role (enum)
+        2 + // record_type (code)
         (4 + 64) + // record_id (String max 64 chars)
-        (4 + 64) + // data_hash (String max 64 chars)
-        (1 + 4 + 100) + // storage_cid (Option<String> max 100 chars)
+        ContentHash::LEN + // data_hash
+        (1 + StorageLocator::LEN) + // storage
         8 + // created_at
         8 + // modified_at
+        8 + // content_updated_at
         8 + // last_accessed
         8 + // access_count
         1 + // is_active
+        (1 + 32) + // deleted_by
+        (1 + 8) + // deleted_at
+        (1 + 8) + // retention_flagged_at
         (1 + 4 + 200) + // metadata (Option<String> max 200 chars)
+        4 + // version
+        4 + // amendment_count
+        1 + // attested
+        (1 + 32) + // attested_by
+        4 + // key_epoch
+        1 + // needs_rekey
+        4 + // attachment_count
+        4 + // next_attachment_index
+        ClinicalCode::LIST_LEN + // clinical_codes
         1; // bump
+
+    /// How long the original deleter may restore a soft-deleted record
+    pub const RESTORE_GRACE_PERIOD_SECONDS: i64 = 7 * 24 * 60 * 60; // 604,800 seconds
+
+    /// Mark the record inactive, remembering who deleted it and when
+    pub fn soft_delete(&mut self, deleter: Pubkey, now: i64) {
+        self.is_active = false;
+        self.deleted_by = Some(deleter);
+        self.deleted_at = Some(now);
+        self.modified_at = now;
+    }
+}
+
+/// Digest of a record's off-chain content and the algorithm that produced it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ContentHash {
+    pub algorithm: HashAlgorithm,
+    pub digest: [u8; 32],
+}
+
+impl ContentHash {
+    pub const LEN: usize = 1 + // algorithm (enum)
+        32; // digest
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HashAlgorithm {
+    Sha256,
+    Blake3,
+    Keccak256,
+}
+
+/// Reason code for restoring a soft-deleted record; deliberately carries no free text
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RestoreReason {
+    /// Record was deleted by mistake
+    DeletedInError,
+    /// Patient asked for the record back
+    PatientRequest,
+    /// Record is still needed for ongoing care
+    ClinicalNeed,
+    /// Record must be kept to meet a legal or regulatory obligation
+    LegalRequirement,
 }
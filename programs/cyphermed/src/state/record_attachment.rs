@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use super::{ContentHash, StorageLocator};
+
+/// Record Attachment - one additional blob (image, scanned page, etc.) of a record
+#[account]
+pub struct RecordAttachment {
+    /// Medical record the attachment belongs to
+    pub record: Pubkey,
+    
+    /// Position among the record's attachments (PDA seed, never reused)
+    pub index: u32,
+    
+    /// Where the encrypted blob is stored
+    pub storage: StorageLocator,
+    
+    /// Digest of the encrypted blob
+    pub data_hash: ContentHash,
+    
+    /// Content type of the blob
+    pub mime_type: MimeType,
+    
+    /// Size of the encrypted blob in bytes
+    pub size_bytes: u64,
+    
+    /// Who added the attachment (refunded when it is removed)
+    pub added_by: Pubkey,
+    
+    /// When the attachment was added
+    pub added_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RecordAttachment {
+    /// Space required for RecordAttachment account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // record
+        4 + // index
+        StorageLocator::LEN + // storage
+        ContentHash::LEN + // data_hash
+        1 + // mime_type (enum)
+        8 + // size_bytes
+        32 + // added_by
+        8 + // added_at
+        1; // bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MimeType {
+    OctetStream,
+    PlainText,
+    Json,
+    FhirJson,
+    Hl7v2,
+    Pdf,
+    Jpeg,
+    Png,
+    Dicom,
+}
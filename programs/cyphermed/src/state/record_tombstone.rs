@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+use super::RecordTypeCode;
+
+/// Record Tombstone - the only trace left of a purged record
+#[account]
+pub struct RecordTombstone {
+    /// Address of the purged record account
+    pub record: Pubkey,
+    
+    /// Patient who owned the record
+    pub patient: Pubkey,
+    
+    /// Type of the purged record
+    pub record_type: RecordTypeCode,
+    
+    /// Purge timestamp
+    pub purged_at: i64,
+    
+    /// Why the record was erased
+    pub reason: ErasureReason,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RecordTombstone {
+    /// Space required for RecordTombstone account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // record
+        32 + // patient
+        2 + // record_type (code)
+        8 + // purged_at
+        1 + // reason (enum)
+        1; // bump
+}
+
+/// Reason code for a hard delete; deliberately carries no free text
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ErasureReason {
+    /// Patient exercised their right to erasure
+    PatientRequest,
+    /// Consent the record was held under was withdrawn
+    ConsentWithdrawn,
+    /// Record was held unlawfully or entered in error
+    UnlawfulProcessing,
+    /// Maximum retention period elapsed
+    RetentionExpired,
+}
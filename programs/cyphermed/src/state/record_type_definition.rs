@@ -0,0 +1,237 @@
+use anchor_lang::prelude::*;
+use super::{RecordType, RetentionPolicy};
+use crate::errors::CypherMedError;
+
+/// Numeric record type, either a built-in `RecordType` or a registered
+/// `RecordTypeDefinition`
+pub type RecordTypeCode = u16;
+
+/// Record Type Definition - a governed record type (Pathology, Genomics, ...)
+///
+/// One account per code, registered by the config authority. Built-in types
+/// need no definition but may be given one to set a name, tier or default
+/// retention.
+#[account]
+pub struct RecordTypeDefinition {
+    /// Code records and grants refer to this type by
+    pub code: RecordTypeCode,
+    
+    /// Human-readable name (e.g. "Genomics")
+    pub name: String,
+    
+    /// How sensitive records of this type are
+    pub sensitivity: SensitivityTier,
+    
+    /// Default seconds after creation before a record may be deleted
+    pub default_min_retention_seconds: Option<i64>,
+    
+    /// Default seconds after creation after which a record should be erased
+    pub default_max_retention_seconds: Option<i64>,
+    
+    /// Retired types cannot be used for new records
+    pub is_active: bool,
+    
+    /// Registration timestamp
+    pub registered_at: i64,
+    
+    /// Last updated timestamp
+    pub updated_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RecordTypeDefinition {
+    pub const MAX_NAME_LEN: usize = 32;
+
+    /// Space required for RecordTypeDefinition account
+    pub const LEN: usize = 8 + // discriminator
+        2 + // code
+        (4 + Self::MAX_NAME_LEN) + // name
+        1 + // sensitivity
+        (1 + 8) + // default_min_retention_seconds
+        (1 + 8) + // default_max_retention_seconds
+        1 + // is_active
+        8 + // registered_at
+        8 + // updated_at
+        1; // bump
+
+    /// Read the definition at its PDA, if one has been registered
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*info.owner, crate::ID, CypherMedError::InvalidRecordType);
+        let data = info.try_borrow_data()?;
+        Ok(Some(Self::try_deserialize(&mut &data[..])?))
+    }
+
+    /// Fail unless new records may use `code`: it must be a built-in type or
+    /// have a definition, and any definition must not be retired
+    pub fn require_usable(code: RecordTypeCode, definition: Option<&Self>) -> Result<()> {
+        match definition {
+            Some(definition) => {
+                require!(definition.code == code, CypherMedError::InvalidRecordType);
+                require!(definition.is_active, CypherMedError::RecordTypeRetired);
+            }
+            None => require!(
+                RecordType::from_code(code).is_some(),
+                CypherMedError::InvalidRecordType
+            ),
+        }
+        Ok(())
+    }
+
+    /// Fail unless every code a grant names is a built-in type or has a
+    /// registered definition
+    ///
+    /// `definitions` holds the definition account of each non-built-in code,
+    /// in the order the codes appear. Retired types may still be granted, as
+    /// their existing records remain readable.
+    pub fn require_grantable(codes: &[RecordTypeCode], definitions: &[AccountInfo]) -> Result<()> {
+        let mut definitions = definitions.iter();
+        for &code in codes.iter().filter(|&&code| RecordType::from_code(code).is_none()) {
+            let info = definitions.next().ok_or(CypherMedError::InvalidRecordType)?;
+            let definition = Self::load(info)?.ok_or(CypherMedError::InvalidRecordType)?;
+            let expected = Pubkey::create_program_address(
+                &[b"record_type".as_ref(), &code.to_le_bytes(), &[definition.bump]],
+                &crate::ID,
+            )
+            .map_err(|_| error!(CypherMedError::InvalidRecordType))?;
+            require_keys_eq!(info.key(), expected, CypherMedError::InvalidRecordType);
+            require!(definition.code == code, CypherMedError::InvalidRecordType);
+        }
+        require!(
+            definitions.next().is_none(),
+            CypherMedError::InvalidRemainingAccounts
+        );
+        Ok(())
+    }
+
+    /// Retention rules to fall back on when the config sets none for the type
+    pub fn default_retention(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            record_type: self.code,
+            min_retention_seconds: self.default_min_retention_seconds,
+            max_retention_seconds: self.default_max_retention_seconds,
+        }
+    }
+}
+
+/// Sensitivity of a record type's content, for clients and indexers
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SensitivityTier {
+    Standard,
+    Sensitive,
+    /// Mental health, genomics, substance use and similar
+    Restricted,
+}
+
+/// Codes of the original built-in types, kept so existing records and
+/// clients keep working
+impl RecordType {
+    pub const fn code(self) -> RecordTypeCode {
+        match self {
+            RecordType::GeneralMedical => 0,
+            RecordType::Prescription => 1,
+            RecordType::LabResult => 2,
+            RecordType::VisitSummary => 3,
+            RecordType::ImmunizationRecord => 4,
+            RecordType::Imaging => 5,
+            RecordType::Emergency => 6,
+        }
+    }
+
+    pub const fn from_code(code: RecordTypeCode) -> Option<Self> {
+        match code {
+            0 => Some(RecordType::GeneralMedical),
+            1 => Some(RecordType::Prescription),
+            2 => Some(RecordType::LabResult),
+            3 => Some(RecordType::VisitSummary),
+            4 => Some(RecordType::ImmunizationRecord),
+            5 => Some(RecordType::Imaging),
+            6 => Some(RecordType::Emergency),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUSTOM: RecordTypeCode = 100;
+
+    fn definition_address(code: RecordTypeCode) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"record_type".as_ref(), &code.to_le_bytes()], &crate::ID)
+    }
+
+    fn definition_data(code: RecordTypeCode, bump: u8) -> Vec<u8> {
+        let definition = RecordTypeDefinition {
+            code,
+            name: "Genomics".to_string(),
+            sensitivity: SensitivityTier::Restricted,
+            default_min_retention_seconds: None,
+            default_max_retention_seconds: None,
+            is_active: false,
+            registered_at: 0,
+            updated_at: 0,
+            bump,
+        };
+        let mut data = Vec::new();
+        definition.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    fn check(codes: &[RecordTypeCode], key: Pubkey, owner: Pubkey, mut data: Vec<u8>) -> Result<()> {
+        let mut lamports = 1;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        RecordTypeDefinition::require_grantable(codes, &[info])
+    }
+
+    #[test]
+    fn built_in_types_need_no_definition() {
+        let codes = [RecordType::GeneralMedical.code(), RecordType::Emergency.code()];
+        assert!(RecordTypeDefinition::require_grantable(&codes, &[]).is_ok());
+    }
+
+    #[test]
+    fn registered_types_are_grantable_even_when_retired() {
+        let (address, bump) = definition_address(CUSTOM);
+        let codes = [RecordType::LabResult.code(), CUSTOM];
+        assert!(check(&codes, address, crate::ID, definition_data(CUSTOM, bump)).is_ok());
+    }
+
+    #[test]
+    fn unregistered_types_are_rejected() {
+        assert_eq!(
+            RecordTypeDefinition::require_grantable(&[CUSTOM], &[]).unwrap_err(),
+            error!(CypherMedError::InvalidRecordType)
+        );
+
+        let (address, _) = definition_address(CUSTOM);
+        assert_eq!(
+            check(&[CUSTOM], address, crate::ID, Vec::new()).unwrap_err(),
+            error!(CypherMedError::InvalidRecordType)
+        );
+    }
+
+    #[test]
+    fn another_code_s_definition_is_rejected() {
+        let (address, bump) = definition_address(CUSTOM + 1);
+        assert_eq!(
+            check(&[CUSTOM], address, crate::ID, definition_data(CUSTOM + 1, bump)).unwrap_err(),
+            error!(CypherMedError::InvalidRecordType)
+        );
+    }
+
+    #[test]
+    fn unused_definitions_are_rejected() {
+        let (address, bump) = definition_address(CUSTOM);
+        assert_eq!(
+            check(&[RecordType::Imaging.code()], address, crate::ID, definition_data(CUSTOM, bump))
+                .unwrap_err(),
+            error!(CypherMedError::InvalidRemainingAccounts)
+        );
+    }
+}
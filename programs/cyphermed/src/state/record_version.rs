@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use super::{ClinicalCode, ContentHash, StorageLocator};
+
+/// Record Version - snapshot of a record's content before an update
+#[account]
+pub struct RecordVersion {
+    /// Medical record this version belongs to
+    pub record: Pubkey,
+    
+    /// Version number that was superseded
+    pub version: u32,
+    
+    /// Data hash of the superseded version
+    pub data_hash: ContentHash,
+    
+    /// Data-key epoch the superseded version is encrypted under
+    pub key_epoch: u32,
+    
+    /// Key that attested the superseded version (None if unattested)
+    pub attested_by: Option<Pubkey>,
+    
+    /// Storage location of the superseded version
+    pub storage: Option<StorageLocator>,
+    
+    /// Metadata of the superseded version
+    pub metadata: Option<String>,
+    
+    /// Clinical codes of the superseded version
+    pub clinical_codes: Vec<ClinicalCode>,
+    
+    /// When the superseded version became current
+    pub valid_from: i64,
+    
+    /// When the superseded version was replaced
+    pub superseded_at: i64,
+    
+    /// Who made the update that replaced this version
+    pub updated_by: Pubkey,
+    
+    /// Note supplied with the update
+    pub update_note: String,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RecordVersion {
+    /// Space required for RecordVersion account
+    pub const LEN: usize = 8 + // discriminator
+        32 + // record
+        4 + // version
+        ContentHash::LEN + // data_hash
+        4 + // key_epoch
+        (1 + 32) + // attested_by
+        (1 + StorageLocator::LEN) + // storage
+        (1 + 4 + 200) + // metadata (Option<String> max 200 chars)
+        ClinicalCode::LIST_LEN + // clinical_codes
+        8 + // valid_from
+        8 + // superseded_at
+        32 + // updated_by
+        (4 + 500) + // update_note (String max 500 chars)
+        1; // bump
+}
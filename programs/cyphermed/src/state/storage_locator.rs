@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+use super::ProgramConfig;
+use crate::errors::CypherMedError;
+
+/// Where a record's encrypted blob lives off-chain
+///
+/// Every variant is paired with the record's (or attachment's) `data_hash`,
+/// so whatever a backend returns can be checked before it is trusted.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub enum StorageLocator {
+    /// IPFS content identifier (CIDv0 `Qm...` or base32 CIDv1 `b...`)
+    Ipfs { cid: String },
+    /// Arweave transaction id (43 base64url characters)
+    Arweave { tx_id: String },
+    /// HTTPS URL (hospital S3 bucket, FHIR server, etc.)
+    Url { url: String },
+    /// Scheme registered in `ProgramConfig::storage_schemes` (on-prem PACS, etc.)
+    Custom { scheme: String, reference: String },
+}
+
+impl StorageLocator {
+    pub const MAX_CID_LEN: usize = 100;
+    pub const ARWEAVE_TX_ID_LEN: usize = 43;
+    pub const MAX_URL_LEN: usize = 200;
+    pub const MAX_SCHEME_LEN: usize = 16;
+    pub const MAX_REFERENCE_LEN: usize = 100;
+
+    /// Space required for the largest variant
+    pub const LEN: usize = 1 + // variant tag
+        (4 + Self::MAX_URL_LEN); // url (largest variant)
+
+    /// Fail unless the locator is well-formed for its backend; custom schemes
+    /// must be registered in `config`
+    pub fn validate(&self, config: Option<&ProgramConfig>) -> Result<()> {
+        match self {
+            StorageLocator::Ipfs { cid } => {
+                let is_v0 = cid.len() == 46
+                    && cid.starts_with("Qm")
+                    && cid.chars().all(is_base58);
+                let is_v1 = cid.len() <= Self::MAX_CID_LEN
+                    && cid.len() > 1
+                    && cid.starts_with('b')
+                    && cid[1..].chars().all(|c| c.is_ascii_lowercase() || ('2'..='7').contains(&c));
+                require!(is_v0 || is_v1, CypherMedError::InvalidIpfsCid);
+            }
+            StorageLocator::Arweave { tx_id } => {
+                require!(
+                    tx_id.len() == Self::ARWEAVE_TX_ID_LEN
+                        && tx_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+                    CypherMedError::InvalidArweaveTxId
+                );
+            }
+            StorageLocator::Url { url } => {
+                require!(
+                    url.len() <= Self::MAX_URL_LEN
+                        && url.len() > "https://".len()
+                        && url.starts_with("https://")
+                        && url.chars().all(|c| c.is_ascii_graphic()),
+                    CypherMedError::InvalidStorageUrl
+                );
+            }
+            StorageLocator::Custom { scheme, reference } => {
+                let registered = config.is_some_and(|config| {
+                    config.storage_schemes.iter().any(|registered| registered == scheme)
+                });
+                require!(registered, CypherMedError::StorageSchemeNotRegistered);
+                require!(
+                    !reference.is_empty()
+                        && reference.len() <= Self::MAX_REFERENCE_LEN
+                        && reference.chars().all(|c| c.is_ascii_graphic()),
+                    CypherMedError::InvalidStorageReference
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `scheme` is an acceptable name for a custom storage scheme
+    pub fn is_valid_scheme_name(scheme: &str) -> bool {
+        !scheme.is_empty()
+            && scheme.len() <= Self::MAX_SCHEME_LEN
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    }
+}
+
+fn is_base58(c: char) -> bool {
+    c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l')
+}
@@ -0,0 +1,295 @@
+//! Offline verification of a patient's hash-chained audit trail.
+
+use anchor_lang::prelude::Pubkey;
+use cyphermed::state::{AuditLog, Patient};
+
+use crate::compressed_audit::CompressedAuditTrail;
+use crate::error::{Result, SdkError};
+use crate::fetcher::AccountFetcher;
+use crate::pda::audit_log_address;
+
+/// Summary of a successfully verified audit chain
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedAuditChain {
+    pub patient: Pubkey,
+    pub entry_count: u64,
+    pub head_hash: [u8; 32],
+}
+
+/// Walk every audit entry of `patient_address` and prove the chain is intact
+///
+/// Entries live at PDAs derived from their sequence number, so walking
+/// `0..audit_count` and checking each link against the patient's head hash
+/// proves that no entry is missing, reordered or modified. Entries written
+/// in compressed mode are taken from `compressed` instead.
+pub fn verify_audit_chain<F: AccountFetcher>(
+    fetcher: &F,
+    patient_address: &Pubkey,
+    compressed: Option<&CompressedAuditTrail>,
+) -> Result<VerifiedAuditChain> {
+    let patient: Patient = fetcher.fetch(patient_address)?;
+    let entries = (0..patient.audit_count).map(|sequence| {
+        if let Some(entry) = compressed.and_then(|trail| trail.entry(sequence)) {
+            return Ok(entry.clone());
+        }
+        fetcher
+            .fetch::<AuditLog>(&audit_log_address(patient_address, sequence))
+            .map_err(|e| match e {
+                SdkError::AccountNotFound(_) => SdkError::MissingAuditEntry(sequence),
+                e => e,
+            })
+    });
+    verify_entries(patient_address, &patient, entries)
+}
+
+/// Verify an already-fetched, sequence-ordered list of entries against `patient`
+pub fn verify_entries<I>(
+    patient_address: &Pubkey,
+    patient: &Patient,
+    entries: I,
+) -> Result<VerifiedAuditChain>
+where
+    I: IntoIterator<Item = Result<AuditLog>>,
+{
+    let mut head = [0u8; 32];
+    let mut count = 0u64;
+
+    for entry in entries {
+        let entry = entry?;
+        if entry.sequence != count {
+            return Err(SdkError::InvalidAuditEntry {
+                sequence: count,
+                reason: "sequence number out of order",
+            });
+        }
+        if entry.patient != *patient_address {
+            return Err(SdkError::InvalidAuditEntry {
+                sequence: count,
+                reason: "entry belongs to a different patient",
+            });
+        }
+        if entry.prev_hash != head {
+            return Err(SdkError::BrokenAuditChain(count));
+        }
+        head = entry.hash();
+        count += 1;
+    }
+
+    if count != patient.audit_count {
+        return Err(SdkError::MissingAuditEntry(count));
+    }
+    if head != patient.audit_head_hash {
+        return Err(SdkError::AuditHeadMismatch);
+    }
+
+    Ok(VerifiedAuditChain {
+        patient: *patient_address,
+        entry_count: count,
+        head_hash: head,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use anchor_lang::AccountSerialize;
+    use cyphermed::state::{AccessAction, AccessDecision, AuditMode, Role};
+
+    use super::*;
+    use crate::compressed_audit::rebuild_compressed_audit;
+
+    const ENTRIES: u64 = 4;
+
+    fn patient_address() -> Pubkey {
+        Pubkey::new_from_array([1; 32])
+    }
+
+    fn serialize<T: AccountSerialize>(account: &T) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    fn new_patient() -> Patient {
+        Patient {
+            authority: Pubkey::new_from_array([2; 32]),
+            name: "Test Patient".to_string(),
+            date_of_birth: 0,
+            created_at: 0,
+            updated_at: 0,
+            record_count: 0,
+            access_grant_count: 0,
+            is_active: true,
+            emergency_contact: None,
+            audit_count: 0,
+            audit_head_hash: [0; 32],
+            audit_mode: AuditMode::Account,
+            audit_tree: None,
+            deletion_cosign_window: None,
+            data_key_epoch: 0,
+            bump: 255,
+        }
+    }
+
+    fn entry(timestamp: i64) -> AuditLog {
+        AuditLog {
+            patient: patient_address(),
+            sequence: 0,
+            prev_hash: [0; 32],
+            record: None,
+            accessor: Pubkey::new_from_array([3; 32]),
+            accessor_role: Role::Doctor,
+            action: AccessAction::View,
+            record_type: None,
+            timestamp,
+            success: true,
+            decision: AccessDecision::Allowed,
+            is_emergency: false,
+            emergency_justification: None,
+            client_info: None,
+            metadata: None,
+            bump: 255,
+        }
+    }
+
+    /// A patient with `ENTRIES` chained entries, linked the way the program links them
+    fn chain() -> (Patient, Vec<AuditLog>) {
+        let mut patient = new_patient();
+        let entries = (0..ENTRIES as i64)
+            .map(|i| {
+                let mut entry = entry(1_000 + i);
+                patient.append_audit(&mut entry).unwrap();
+                entry
+            })
+            .collect();
+        (patient, entries)
+    }
+
+    fn accounts(patient: &Patient, entries: &[AuditLog]) -> HashMap<Pubkey, Vec<u8>> {
+        let mut accounts = HashMap::new();
+        accounts.insert(patient_address(), serialize(patient));
+        for entry in entries {
+            accounts.insert(
+                audit_log_address(&patient_address(), entry.sequence),
+                serialize(entry),
+            );
+        }
+        accounts
+    }
+
+    fn verify(entries: Vec<AuditLog>, patient: &Patient) -> Result<VerifiedAuditChain> {
+        verify_entries(&patient_address(), patient, entries.into_iter().map(Ok))
+    }
+
+    #[test]
+    fn valid_chain_verifies() {
+        let (patient, entries) = chain();
+        let verified = verify_audit_chain(&accounts(&patient, &entries), &patient_address(), None).unwrap();
+        assert_eq!(verified.entry_count, ENTRIES);
+        assert_eq!(verified.head_hash, patient.audit_head_hash);
+    }
+
+    #[test]
+    fn compressed_entries_are_taken_from_the_trail() {
+        let (patient, entries) = chain();
+        let payloads: Vec<Vec<u8>> = entries[1..3].iter().map(serialize).collect();
+        let trail = rebuild_compressed_audit(&patient_address(), payloads.iter().map(Vec::as_slice)).unwrap();
+
+        // Only the entries written in account mode exist as accounts
+        let account_entries = [entries[0].clone(), entries[3].clone()];
+        let accounts = accounts(&patient, &account_entries);
+        let verified = verify_audit_chain(&accounts, &patient_address(), Some(&trail)).unwrap();
+        assert_eq!(verified.entry_count, ENTRIES);
+        assert_eq!(verified.head_hash, patient.audit_head_hash);
+
+        assert!(matches!(
+            verify_audit_chain(&accounts, &patient_address(), None),
+            Err(SdkError::MissingAuditEntry(1))
+        ));
+    }
+
+    #[test]
+    fn tampered_compressed_entry_is_rejected() {
+        let (patient, mut entries) = chain();
+        // The last entry has no successor in the trail, so only the head catches it
+        entries[3].success = false;
+        let payloads: Vec<Vec<u8>> = entries[1..].iter().map(serialize).collect();
+        let trail = rebuild_compressed_audit(&patient_address(), payloads.iter().map(Vec::as_slice)).unwrap();
+        assert!(matches!(
+            verify_audit_chain(&accounts(&patient, &entries[..1]), &patient_address(), Some(&trail)),
+            Err(SdkError::AuditHeadMismatch)
+        ));
+    }
+
+    #[test]
+    fn tampered_entry_breaks_the_next_link() {
+        let (patient, mut entries) = chain();
+        entries[1].success = false;
+        assert!(matches!(
+            verify_audit_chain(&accounts(&patient, &entries), &patient_address(), None),
+            Err(SdkError::BrokenAuditChain(2))
+        ));
+
+        // Tampering with the last entry only shows at the head
+        let (patient, mut entries) = chain();
+        entries[3].metadata = Some("edited".to_string());
+        assert!(matches!(verify(entries, &patient), Err(SdkError::AuditHeadMismatch)));
+    }
+
+    #[test]
+    fn missing_entry_is_reported() {
+        let (patient, entries) = chain();
+        let mut accounts = accounts(&patient, &entries);
+        accounts.remove(&audit_log_address(&patient_address(), 2));
+        assert!(matches!(
+            verify_audit_chain(&accounts, &patient_address(), None),
+            Err(SdkError::MissingAuditEntry(2))
+        ));
+
+        // A truncated list falls short of the patient's count
+        let (patient, mut entries) = chain();
+        entries.pop();
+        assert!(matches!(
+            verify(entries, &patient),
+            Err(SdkError::MissingAuditEntry(3))
+        ));
+    }
+
+    #[test]
+    fn reordered_entries_are_rejected() {
+        let (patient, mut entries) = chain();
+        entries.swap(1, 2);
+        assert!(matches!(
+            verify(entries, &patient),
+            Err(SdkError::InvalidAuditEntry { sequence: 1, .. })
+        ));
+
+        // Renumbering the swapped entries still leaves the links broken
+        let (patient, mut entries) = chain();
+        entries.swap(1, 2);
+        entries[1].sequence = 1;
+        entries[2].sequence = 2;
+        assert!(matches!(verify(entries, &patient), Err(SdkError::BrokenAuditChain(1))));
+    }
+
+    #[test]
+    fn head_hash_mismatch_is_rejected() {
+        let (mut patient, entries) = chain();
+        patient.audit_head_hash = [9; 32];
+        assert!(matches!(
+            verify_audit_chain(&accounts(&patient, &entries), &patient_address(), None),
+            Err(SdkError::AuditHeadMismatch)
+        ));
+    }
+
+    #[test]
+    fn entries_of_another_patient_are_rejected() {
+        let (patient, mut entries) = chain();
+        entries[0].patient = Pubkey::new_from_array([7; 32]);
+        assert!(matches!(
+            verify(entries, &patient),
+            Err(SdkError::InvalidAuditEntry { sequence: 0, .. })
+        ));
+    }
+}
@@ -0,0 +1,350 @@
+//! Rebuilding compressed audit entries from transaction history.
+//!
+//! In compressed mode each audit entry is serialized into a noop instruction
+//! and its chain hash is appended as a leaf of the patient's concurrent
+//! Merkle tree. Given the noop payloads, this module decodes the entries,
+//! checks their links and recomputes the tree root.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use cyphermed::state::AuditLog;
+use sha3::{Digest, Keccak256};
+
+use crate::error::{Result, SdkError};
+
+/// Compressed audit entries for one patient, in sequence order
+#[derive(Clone, Default)]
+pub struct CompressedAuditTrail {
+    pub entries: Vec<AuditLog>,
+    pub leaves: Vec<[u8; 32]>,
+}
+
+impl CompressedAuditTrail {
+    /// Entry with the given audit sequence number, if it was compressed
+    pub fn entry(&self, sequence: u64) -> Option<&AuditLog> {
+        self.entries
+            .binary_search_by_key(&sequence, |e| e.sequence)
+            .ok()
+            .map(|i| &self.entries[i])
+    }
+
+    /// Root of a tree of `max_depth` after appending every leaf in order
+    pub fn root(&self, max_depth: u32) -> Result<[u8; 32]> {
+        merkle_root(&self.leaves, max_depth)
+    }
+
+    /// Check the rebuilt leaves against the tree's current on-chain root
+    pub fn verify_root(&self, max_depth: u32, expected_root: &[u8; 32]) -> Result<()> {
+        if self.root(max_depth)? != *expected_root {
+            return Err(SdkError::MerkleRootMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Whether a noop payload carries a CypherMed audit entry
+pub fn is_audit_entry(payload: &[u8]) -> bool {
+    payload.starts_with(AuditLog::DISCRIMINATOR)
+}
+
+/// Decode an audit entry logged through the noop program
+pub fn decode_audit_entry(payload: &[u8]) -> Result<AuditLog> {
+    AuditLog::try_deserialize(&mut &payload[..])
+        .map_err(|e| SdkError::InvalidAuditPayload(e.to_string()))
+}
+
+/// Rebuild a patient's compressed audit trail from noop payloads
+///
+/// `payloads` must be in transaction order. Payloads that are not audit
+/// entries (e.g. account compression change logs) or that belong to other
+/// patients are skipped. Consecutive entries must link by `prev_hash`; gaps
+/// are allowed because the patient may have used account mode in between.
+pub fn rebuild_compressed_audit<'a, I>(patient: &Pubkey, payloads: I) -> Result<CompressedAuditTrail>
+where
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    let mut trail = CompressedAuditTrail::default();
+
+    for payload in payloads.into_iter().filter(|p| is_audit_entry(p)) {
+        let entry = decode_audit_entry(payload)?;
+        if entry.patient != *patient {
+            continue;
+        }
+
+        if let Some(previous) = trail.entries.last() {
+            if entry.sequence <= previous.sequence {
+                return Err(SdkError::InvalidAuditEntry {
+                    sequence: entry.sequence,
+                    reason: "sequence number out of order",
+                });
+            }
+            let previous_leaf = trail.leaves[trail.leaves.len() - 1];
+            if entry.sequence == previous.sequence + 1 && entry.prev_hash != previous_leaf {
+                return Err(SdkError::BrokenAuditChain(entry.sequence));
+            }
+        }
+
+        trail.leaves.push(entry.hash());
+        trail.entries.push(entry);
+    }
+
+    Ok(trail)
+}
+
+/// Root of a concurrent Merkle tree of `max_depth` holding `leaves` in order
+///
+/// Matches SPL account compression: keccak-256 over `left || right`, with
+/// empty leaves as all zeroes.
+pub fn merkle_root(leaves: &[[u8; 32]], max_depth: u32) -> Result<[u8; 32]> {
+    if max_depth < usize::BITS && leaves.len() > 1usize << max_depth {
+        return Err(SdkError::TreeCapacityExceeded);
+    }
+
+    let mut level = leaves.to_vec();
+    let mut empty = [0u8; 32];
+    for _ in 0..max_depth {
+        level = level
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&empty)))
+            .collect();
+        empty = hash_pair(&empty, &empty);
+    }
+
+    Ok(level.first().copied().unwrap_or(empty))
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AccountSerialize;
+    use cyphermed::state::{AccessAction, AccessDecision, AuditMode, Patient, Role};
+
+    use super::*;
+
+    /// Smallest depth `init_empty_merkle_tree` accepts (paired with a buffer of 8)
+    const TEST_TREE_DEPTH: u32 = 3;
+
+    const ENTRIES: u64 = 4;
+
+    fn hex(bytes: &[u8; 32]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn leaf(i: u8) -> [u8; 32] {
+        [i + 1; 32]
+    }
+
+    fn patient_address() -> Pubkey {
+        Pubkey::new_from_array([1; 32])
+    }
+
+    fn new_patient() -> Patient {
+        Patient {
+            authority: Pubkey::new_from_array([2; 32]),
+            name: "Test Patient".to_string(),
+            date_of_birth: 0,
+            created_at: 0,
+            updated_at: 0,
+            record_count: 0,
+            access_grant_count: 0,
+            is_active: true,
+            emergency_contact: None,
+            audit_count: 0,
+            audit_head_hash: [0; 32],
+            audit_mode: AuditMode::Compressed,
+            audit_tree: Some(Pubkey::new_from_array([4; 32])),
+            deletion_cosign_window: None,
+            data_key_epoch: 0,
+            bump: 255,
+        }
+    }
+
+    /// `ENTRIES` chained entries, linked the way the program links them
+    fn chain() -> Vec<AuditLog> {
+        let mut patient = new_patient();
+        (0..ENTRIES as i64)
+            .map(|i| {
+                let mut entry = AuditLog {
+                    patient: patient_address(),
+                    sequence: 0,
+                    prev_hash: [0; 32],
+                    record: None,
+                    accessor: Pubkey::new_from_array([3; 32]),
+                    accessor_role: Role::Doctor,
+                    action: AccessAction::View,
+                    record_type: None,
+                    timestamp: 1_000 + i,
+                    success: true,
+                    decision: AccessDecision::Allowed,
+                    is_emergency: false,
+                    emergency_justification: None,
+                    client_info: None,
+                    metadata: None,
+                    bump: 0,
+                };
+                patient.append_audit(&mut entry).unwrap();
+                entry
+            })
+            .collect()
+    }
+
+    /// The noop payload the program logs for `entry`
+    fn payload(entry: &AuditLog) -> Vec<u8> {
+        let mut data = Vec::new();
+        entry.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    fn rebuild(payloads: &[Vec<u8>]) -> Result<CompressedAuditTrail> {
+        rebuild_compressed_audit(&patient_address(), payloads.iter().map(Vec::as_slice))
+    }
+
+    #[test]
+    fn payloads_decode_back_to_their_entries() {
+        let entries = chain();
+        let payload = payload(&entries[1]);
+        assert!(is_audit_entry(&payload));
+        let decoded = decode_audit_entry(&payload).unwrap();
+        assert_eq!(decoded.sequence, 1);
+        assert_eq!(decoded.hash(), entries[1].hash());
+
+        // Change logs and truncated payloads are not audit entries
+        assert!(!is_audit_entry(&[0; 16]));
+        assert!(matches!(
+            decode_audit_entry(&payload[..payload.len() - 1]),
+            Err(SdkError::InvalidAuditPayload(_))
+        ));
+    }
+
+    #[test]
+    fn rebuilt_trail_matches_the_logged_entries() {
+        let entries = chain();
+        let mut payloads: Vec<Vec<u8>> = entries.iter().map(payload).collect();
+        // Interleave payloads the rebuild must skip
+        payloads.insert(1, vec![0; 40]);
+        let mut other = entries[0].clone();
+        other.patient = Pubkey::new_from_array([7; 32]);
+        payloads.insert(3, payload(&other));
+
+        let trail = rebuild(&payloads).unwrap();
+        let leaves: Vec<[u8; 32]> = entries.iter().map(AuditLog::hash).collect();
+        assert_eq!(trail.leaves, leaves);
+        assert_eq!(trail.entries.len(), ENTRIES as usize);
+        assert_eq!(trail.entry(2).unwrap().timestamp, entries[2].timestamp);
+
+        let root = merkle_root(&leaves, TEST_TREE_DEPTH).unwrap();
+        assert_eq!(trail.root(TEST_TREE_DEPTH).unwrap(), root);
+        assert!(trail.verify_root(TEST_TREE_DEPTH, &root).is_ok());
+    }
+
+    #[test]
+    fn tampered_entry_is_rejected() {
+        let mut entries = chain();
+        entries[1].success = false;
+        let payloads: Vec<Vec<u8>> = entries.iter().map(payload).collect();
+        assert!(matches!(rebuild(&payloads), Err(SdkError::BrokenAuditChain(2))));
+
+        // The last entry has no successor, so tampering shows in the root
+        let entries = chain();
+        let root = rebuild(&entries.iter().map(payload).collect::<Vec<_>>())
+            .unwrap()
+            .root(TEST_TREE_DEPTH)
+            .unwrap();
+        let mut tampered = entries;
+        tampered[3].metadata = Some("edited".to_string());
+        let trail = rebuild(&tampered.iter().map(payload).collect::<Vec<_>>()).unwrap();
+        assert!(matches!(
+            trail.verify_root(TEST_TREE_DEPTH, &root),
+            Err(SdkError::MerkleRootMismatch)
+        ));
+    }
+
+    #[test]
+    fn reordered_entries_are_rejected() {
+        let mut payloads: Vec<Vec<u8>> = chain().iter().map(payload).collect();
+        payloads.swap(1, 2);
+        assert!(matches!(
+            rebuild(&payloads),
+            Err(SdkError::InvalidAuditEntry { sequence: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn hash_pair_is_keccak256() {
+        // keccak256 of 64 zero bytes, the first empty-subtree node
+        assert_eq!(
+            hex(&hash_pair(&[0; 32], &[0; 32])),
+            "ad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"
+        );
+    }
+
+    #[test]
+    fn empty_tree_root_is_the_zero_subtree_of_its_depth() {
+        assert_eq!(merkle_root(&[], 0).unwrap(), [0; 32]);
+        assert_eq!(
+            hex(&merkle_root(&[], 1).unwrap()),
+            "ad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"
+        );
+        assert_eq!(
+            hex(&merkle_root(&[], 2).unwrap()),
+            "b4c11951957c6f8f642c4af61cd6b24640fec6dc7fc607ee8206a99e92410d30"
+        );
+
+        let mut empty = [0u8; 32];
+        for _ in 0..TEST_TREE_DEPTH {
+            empty = hash_pair(&empty, &empty);
+        }
+        assert_eq!(merkle_root(&[], TEST_TREE_DEPTH).unwrap(), empty);
+    }
+
+    #[test]
+    fn missing_leaves_are_zero_padded() {
+        let leaves = [leaf(0), leaf(1), leaf(2)];
+        let zero = [0u8; 32];
+        let expected = hash_pair(
+            &hash_pair(
+                &hash_pair(&leaves[0], &leaves[1]),
+                &hash_pair(&leaves[2], &zero),
+            ),
+            &hash_pair(&hash_pair(&zero, &zero), &hash_pair(&zero, &zero)),
+        );
+        assert_eq!(merkle_root(&leaves, TEST_TREE_DEPTH).unwrap(), expected);
+
+        let mut padded = leaves.to_vec();
+        padded.resize(1 << TEST_TREE_DEPTH, zero);
+        assert_eq!(merkle_root(&padded, TEST_TREE_DEPTH).unwrap(), expected);
+    }
+
+    #[test]
+    fn tree_capacity_follows_its_depth() {
+        let full: Vec<[u8; 32]> = (0..1u8 << TEST_TREE_DEPTH).map(leaf).collect();
+        assert!(merkle_root(&full, TEST_TREE_DEPTH).is_ok());
+
+        let mut overfull = full;
+        overfull.push(leaf(99));
+        assert!(matches!(
+            merkle_root(&overfull, TEST_TREE_DEPTH),
+            Err(SdkError::TreeCapacityExceeded)
+        ));
+    }
+
+    #[test]
+    fn trail_root_is_checked_against_the_on_chain_root() {
+        let trail = CompressedAuditTrail {
+            entries: Vec::new(),
+            leaves: vec![leaf(0), leaf(1)],
+        };
+        let root = merkle_root(&trail.leaves, TEST_TREE_DEPTH).unwrap();
+        assert!(trail.verify_root(TEST_TREE_DEPTH, &root).is_ok());
+        assert!(matches!(
+            trail.verify_root(TEST_TREE_DEPTH + 1, &root),
+            Err(SdkError::MerkleRootMismatch)
+        ));
+    }
+}
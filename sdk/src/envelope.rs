@@ -0,0 +1,141 @@
+//! Wrapping a patient's data key to a grantee's X25519 key.
+//!
+//! The sender generates a one-time X25519 key, derives a wrapping key from
+//! the shared secret with HKDF-SHA256 and seals the data key with
+//! ChaCha20-Poly1305. The result is the `WrappedKey` stored in a
+//! `KeyEnvelope` account; only the holder of the recipient's X25519 secret
+//! can open it.
+
+use anchor_lang::prelude::Pubkey;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use cyphermed::state::WrappedKey;
+use hkdf::Hkdf;
+use rand_core::{OsRng, RngCore};
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::error::{Result, SdkError};
+
+/// HKDF `info` binding derived keys to this envelope format
+const ENVELOPE_INFO: &[u8] = b"cyphermed key envelope v1";
+
+/// A fresh random 32-byte data key
+pub fn generate_data_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+/// A fresh X25519 secret; register its public half with `register_encryption_key`
+pub fn generate_x25519_secret() -> [u8; 32] {
+    StaticSecret::random_from_rng(OsRng).to_bytes()
+}
+
+/// X25519 public key of `secret`
+pub fn x25519_public_key(secret: &[u8; 32]) -> [u8; 32] {
+    PublicKey::from(&StaticSecret::from(*secret)).to_bytes()
+}
+
+/// Bytes the wallet signs (via the Ed25519 program) when registering
+/// `public_key` as its encryption key number `key_id`
+pub fn encryption_key_binding_message(owner: &Pubkey, key_id: u32, public_key: &[u8; 32]) -> Vec<u8> {
+    cyphermed::attestation::encryption_key_binding_message(owner, key_id, public_key)
+}
+
+/// Seal `data_key` so only the holder of `recipient`'s secret can open it
+pub fn wrap_data_key(data_key: &[u8; 32], recipient: &[u8; 32]) -> WrappedKey {
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    seal(data_key, recipient, &ephemeral, nonce)
+}
+
+/// Open an envelope with the recipient's X25519 secret
+pub fn unwrap_data_key(wrapped: &WrappedKey, recipient_secret: &[u8; 32]) -> Result<[u8; 32]> {
+    let secret = StaticSecret::from(*recipient_secret);
+    let recipient = PublicKey::from(&secret).to_bytes();
+    let shared = secret.diffie_hellman(&PublicKey::from(wrapped.ephemeral_public_key));
+
+    let cipher = ChaCha20Poly1305::new(&wrapping_key(
+        shared.as_bytes(),
+        &wrapped.ephemeral_public_key,
+        &recipient,
+    ));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&wrapped.nonce), wrapped.ciphertext.as_slice())
+        .map_err(|_| SdkError::EnvelopeDecryption)?;
+    plaintext
+        .try_into()
+        .map_err(|_| SdkError::EnvelopeDecryption)
+}
+
+fn seal(
+    data_key: &[u8; 32],
+    recipient: &[u8; 32],
+    ephemeral: &StaticSecret,
+    nonce: [u8; 12],
+) -> WrappedKey {
+    let ephemeral_public_key = PublicKey::from(ephemeral).to_bytes();
+    let shared = ephemeral.diffie_hellman(&PublicKey::from(*recipient));
+
+    let cipher = ChaCha20Poly1305::new(&wrapping_key(
+        shared.as_bytes(),
+        &ephemeral_public_key,
+        recipient,
+    ));
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), data_key.as_slice())
+        .expect("sealing a 32-byte key cannot fail");
+
+    WrappedKey {
+        ephemeral_public_key,
+        nonce,
+        ciphertext: sealed.try_into().expect("32-byte key + 16-byte tag"),
+    }
+}
+
+fn wrapping_key(shared: &[u8; 32], ephemeral: &[u8; 32], recipient: &[u8; 32]) -> Key {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral);
+    salt[32..].copy_from_slice(recipient);
+
+    let mut key = Key::default();
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(ENVELOPE_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recipient_unwraps_the_data_key() {
+        let secret = generate_x25519_secret();
+        let data_key = generate_data_key();
+
+        let wrapped = wrap_data_key(&data_key, &x25519_public_key(&secret));
+        assert_eq!(unwrap_data_key(&wrapped, &secret).unwrap(), data_key);
+    }
+
+    #[test]
+    fn other_keys_and_tampering_are_rejected() {
+        let secret = generate_x25519_secret();
+        let data_key = generate_data_key();
+        let mut wrapped = wrap_data_key(&data_key, &x25519_public_key(&secret));
+
+        let stranger = generate_x25519_secret();
+        assert!(matches!(
+            unwrap_data_key(&wrapped, &stranger),
+            Err(SdkError::EnvelopeDecryption)
+        ));
+
+        wrapped.ciphertext[0] ^= 1;
+        assert!(matches!(
+            unwrap_data_key(&wrapped, &secret),
+            Err(SdkError::EnvelopeDecryption)
+        ));
+    }
+}
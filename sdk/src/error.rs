@@ -0,0 +1,61 @@
+use anchor_lang::prelude::Pubkey;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SdkError {
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+
+    #[error("account {0} could not be deserialized: {1}")]
+    InvalidAccountData(Pubkey, String),
+
+    #[error("version {requested} does not exist (latest is {latest})")]
+    VersionNotFound { requested: u32, latest: u32 },
+
+    #[error("record did not exist at timestamp {0}")]
+    TimestampBeforeCreation(i64),
+
+    #[error("audit entry {0} is missing")]
+    MissingAuditEntry(u64),
+
+    #[error("audit entry {sequence} is invalid: {reason}")]
+    InvalidAuditEntry { sequence: u64, reason: &'static str },
+
+    #[error("audit entry {0} does not link to its predecessor")]
+    BrokenAuditChain(u64),
+
+    #[error("audit chain does not end at the patient's head hash")]
+    AuditHeadMismatch,
+
+    #[error("noop payload is not a valid audit entry: {0}")]
+    InvalidAuditPayload(String),
+
+    #[error("rebuilt leaves do not match the audit tree root")]
+    MerkleRootMismatch,
+
+    #[error("more leaves than the audit tree can hold")]
+    TreeCapacityExceeded,
+
+    #[error("fetch failed: {0}")]
+    Fetch(String),
+
+    #[error("blob not found at {0}")]
+    BlobNotFound(String),
+
+    #[error("blob at {0} does not match its recorded content hash")]
+    ContentHashMismatch(String),
+
+    #[error("storage backend failed: {0}")]
+    Storage(String),
+
+    #[error("key envelope could not be opened with this key")]
+    EnvelopeDecryption,
+
+    #[error("invalid key shares: {0}")]
+    InvalidShares(&'static str),
+
+    #[error("share {0} does not match its on-chain commitment")]
+    ShareCommitmentMismatch(u8),
+}
+
+pub type Result<T> = std::result::Result<T, SdkError>;
@@ -0,0 +1,284 @@
+//! Shamir secret sharing of an emergency decryption key.
+//!
+//! A 32-byte key is split byte-wise over GF(2^8) (the AES field, reduction
+//! polynomial x^8 + x^4 + x^3 + x + 1) into shares indexed 1..=255. Any
+//! `threshold` of them recombine to the key; fewer reveal nothing about it.
+//! Each share is wrapped to its custodian with [`crate::envelope::wrap_data_key`]
+//! and committed to on-chain with [`share_commitment`].
+
+use anchor_lang::prelude::Pubkey;
+use cyphermed::state::{EscrowShareInput, KeyEscrow};
+use rand_core::{CryptoRng, OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::envelope::wrap_data_key;
+use crate::error::{Result, SdkError};
+
+/// Domain separator for share commitments
+const COMMITMENT_DOMAIN: &[u8] = b"cyphermed-escrow-share";
+
+/// One point of the sharing polynomial for every byte of the key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SecretShare {
+    /// x-coordinate (never 0; 0 is the secret itself)
+    pub index: u8,
+    pub data: [u8; 32],
+}
+
+/// A custodian and the X25519 key their share is wrapped to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Custodian {
+    pub authority: Pubkey,
+    pub recipient_key: [u8; 32],
+}
+
+/// Split `secret` into `shares` shares, any `threshold` of which recover it
+pub fn split_secret(secret: &[u8; 32], threshold: u8, shares: u8) -> Result<Vec<SecretShare>> {
+    split_secret_with_rng(secret, threshold, shares, &mut OsRng)
+}
+
+/// [`split_secret`] with a caller-supplied randomness source
+pub fn split_secret_with_rng<R: RngCore + CryptoRng>(
+    secret: &[u8; 32],
+    threshold: u8,
+    shares: u8,
+    rng: &mut R,
+) -> Result<Vec<SecretShare>> {
+    if threshold < 2 || threshold > shares {
+        return Err(SdkError::InvalidShares("threshold must be between 2 and the share count"));
+    }
+
+    // coefficients[i][0] is the secret byte; the rest are random
+    let mut coefficients = vec![[0u8; 32]; threshold as usize];
+    coefficients[0] = *secret;
+    for row in coefficients.iter_mut().skip(1) {
+        rng.fill_bytes(row);
+    }
+
+    let shares = (1..=shares)
+        .map(|index| {
+            let mut data = [0u8; 32];
+            for (byte, out) in data.iter_mut().enumerate() {
+                // Horner's rule from the highest coefficient down
+                *out = coefficients
+                    .iter()
+                    .rev()
+                    .fold(0, |acc, row| gf_mul(acc, index) ^ row[byte]);
+            }
+            SecretShare { index, data }
+        })
+        .collect();
+
+    for row in coefficients.iter_mut() {
+        row.fill(0);
+    }
+    Ok(shares)
+}
+
+/// Recover the secret from at least `threshold` distinct shares
+///
+/// With fewer shares than the threshold the result is a wrong key, not an
+/// error; check it against the shares' commitments or the data it decrypts.
+pub fn combine_shares(shares: &[SecretShare]) -> Result<[u8; 32]> {
+    if shares.len() < 2 {
+        return Err(SdkError::InvalidShares("at least two shares are required"));
+    }
+    for (i, share) in shares.iter().enumerate() {
+        if share.index == 0 || shares[..i].iter().any(|other| other.index == share.index) {
+            return Err(SdkError::InvalidShares("share indexes must be distinct and non-zero"));
+        }
+    }
+
+    // Lagrange interpolation at x = 0; subtraction is XOR in GF(2^8)
+    let mut secret = [0u8; 32];
+    for share in shares {
+        let mut basis = 1u8;
+        for other in shares.iter().filter(|other| other.index != share.index) {
+            basis = gf_mul(basis, gf_div(other.index, other.index ^ share.index));
+        }
+        for (out, byte) in secret.iter_mut().zip(share.data.iter()) {
+            *out ^= gf_mul(basis, *byte);
+        }
+    }
+    Ok(secret)
+}
+
+/// Commitment to a share, recorded on-chain in `EscrowCustodian::commitment`
+pub fn share_commitment(share: &SecretShare) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(COMMITMENT_DOMAIN);
+    hasher.update([share.index]);
+    hasher.update(share.data);
+    hasher.finalize().into()
+}
+
+/// Split `secret` for `custodians` and wrap each share to its custodian,
+/// producing the arguments for `configure_key_escrow`
+pub fn escrow_shares(
+    secret: &[u8; 32],
+    threshold: u8,
+    custodians: &[Custodian],
+) -> Result<Vec<EscrowShareInput>> {
+    if custodians.len() > KeyEscrow::MAX_CUSTODIANS {
+        return Err(SdkError::InvalidShares("too many custodians"));
+    }
+    let shares = split_secret(secret, threshold, custodians.len() as u8)?;
+    Ok(custodians
+        .iter()
+        .zip(shares.iter())
+        .map(|(custodian, share)| EscrowShareInput {
+            custodian: custodian.authority,
+            recipient_key: custodian.recipient_key,
+            share_index: share.index,
+            commitment: share_commitment(share),
+            wrapped_share: wrap_data_key(&share.data, &custodian.recipient_key),
+        })
+        .collect())
+}
+
+/// Recover the escrowed key from shares opened by the responder, checking
+/// each against its on-chain commitment first
+pub fn recover_escrowed_key(escrow: &KeyEscrow, shares: &[SecretShare]) -> Result<[u8; 32]> {
+    if shares.len() < escrow.threshold as usize {
+        return Err(SdkError::InvalidShares("fewer shares than the escrow threshold"));
+    }
+    for share in shares {
+        let committed = escrow
+            .custodians
+            .iter()
+            .any(|entry| entry.share_index == share.index && entry.commitment == share_commitment(share));
+        if !committed {
+            return Err(SdkError::ShareCommitmentMismatch(share.index));
+        }
+    }
+    combine_shares(shares)
+}
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// a^254 = a^-1 for non-zero a
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exponent = 254u8;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+    gf_mul(a, gf_inv(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift source so the tests need nothing from the OS
+    struct TestRng(u64);
+
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for chunk in dest.chunks_mut(8) {
+                let bytes = self.next_u64().to_le_bytes();
+                chunk.copy_from_slice(&bytes[..chunk.len()]);
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for TestRng {}
+
+    fn secret() -> [u8; 32] {
+        core::array::from_fn(|i| (i as u8).wrapping_mul(37).wrapping_add(11))
+    }
+
+    #[test]
+    fn field_arithmetic_matches_the_aes_field() {
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn any_threshold_subset_recovers_the_secret() {
+        let shares = split_secret_with_rng(&secret(), 3, 5, &mut TestRng(0x9e37_79b9_7f4a_7c15)).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        for a in 0..5 {
+            for b in a + 1..5 {
+                for c in b + 1..5 {
+                    let subset = [shares[a], shares[b], shares[c]];
+                    assert_eq!(combine_shares(&subset).unwrap(), secret());
+                }
+            }
+        }
+        assert_eq!(combine_shares(&shares).unwrap(), secret());
+    }
+
+    #[test]
+    fn fewer_shares_than_the_threshold_do_not_recover_the_secret() {
+        let shares = split_secret_with_rng(&secret(), 3, 5, &mut TestRng(42)).unwrap();
+        assert_ne!(combine_shares(&shares[..2]).unwrap(), secret());
+    }
+
+    #[test]
+    fn invalid_parameters_and_shares_are_rejected() {
+        let mut rng = TestRng(7);
+        assert!(split_secret_with_rng(&secret(), 1, 3, &mut rng).is_err());
+        assert!(split_secret_with_rng(&secret(), 4, 3, &mut rng).is_err());
+
+        let shares = split_secret_with_rng(&secret(), 2, 3, &mut rng).unwrap();
+        assert!(combine_shares(&[shares[0], shares[0]]).is_err());
+        assert!(combine_shares(&[SecretShare { index: 0, data: [0; 32] }, shares[1]]).is_err());
+    }
+
+    #[test]
+    fn commitments_bind_index_and_data() {
+        let shares = split_secret_with_rng(&secret(), 2, 2, &mut TestRng(99)).unwrap();
+        let commitment = share_commitment(&shares[0]);
+
+        let mut tampered = shares[0];
+        tampered.data[0] ^= 1;
+        assert_ne!(share_commitment(&tampered), commitment);
+
+        let moved = SecretShare { index: 2, data: shares[0].data };
+        assert_ne!(share_commitment(&moved), commitment);
+    }
+}
@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+
+use crate::error::{Result, SdkError};
+
+/// Source of raw account data (an RPC client, a snapshot, a test fixture...)
+pub trait AccountFetcher {
+    /// Return the account's data, or `None` if the account does not exist
+    fn fetch_account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>>;
+
+    /// Fetch and deserialize an Anchor account
+    fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        let data = self
+            .fetch_account_data(address)?
+            .ok_or(SdkError::AccountNotFound(*address))?;
+        T::try_deserialize(&mut data.as_slice())
+            .map_err(|e| SdkError::InvalidAccountData(*address, e.to_string()))
+    }
+}
+
+impl AccountFetcher for HashMap<Pubkey, Vec<u8>> {
+    fn fetch_account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>> {
+        Ok(self.get(address).cloned())
+    }
+}
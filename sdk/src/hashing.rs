@@ -0,0 +1,83 @@
+//! Content digests matching `MedicalRecord::data_hash`.
+//!
+//! Hash the exact bytes that were stored off-chain (the encrypted blob), then
+//! pass the result to `create_record`, `update_record` or
+//! `verify_record_integrity`.
+
+use anchor_lang::prelude::Pubkey;
+use cyphermed::state::{ContentHash, HashAlgorithm};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+/// Digest `data` with `algorithm`
+pub fn content_hash(algorithm: HashAlgorithm, data: &[u8]) -> ContentHash {
+    let digest = match algorithm {
+        HashAlgorithm::Sha256 => Sha256::digest(data).into(),
+        HashAlgorithm::Blake3 => *blake3::hash(data).as_bytes(),
+        HashAlgorithm::Keccak256 => Keccak256::digest(data).into(),
+    };
+    ContentHash { algorithm, digest }
+}
+
+/// Whether `data` hashes to `expected` under the algorithm it names
+pub fn matches_content_hash(expected: &ContentHash, data: &[u8]) -> bool {
+    content_hash(expected.algorithm, data) == *expected
+}
+
+/// Bytes a provider signs with their registered key to attest `data_hash`
+/// as version `version` of `record`
+///
+/// Sign these with Ed25519 and place the Ed25519 program's verify
+/// instruction immediately before `create_record` or `update_record`.
+pub fn attestation_message(record: &Pubkey, data_hash: &ContentHash, version: u32) -> Vec<u8> {
+    cyphermed::attestation::attestation_message(record, data_hash, version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8; 32]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn content_hash_matches_known_answers() {
+        let cases = [
+            (
+                HashAlgorithm::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashAlgorithm::Blake3,
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            ),
+            (
+                HashAlgorithm::Keccak256,
+                "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            ),
+        ];
+        for (algorithm, expected) in cases {
+            let hash = content_hash(algorithm, b"abc");
+            assert_eq!(hash.algorithm, algorithm);
+            assert_eq!(hex(&hash.digest), expected, "{:?}", algorithm);
+            assert!(matches_content_hash(&hash, b"abc"));
+        }
+    }
+
+    #[test]
+    fn mismatched_content_is_rejected() {
+        let hash = content_hash(HashAlgorithm::Sha256, b"abc");
+        assert!(!matches_content_hash(&hash, b"abd"));
+        assert!(!matches_content_hash(&hash, b""));
+    }
+
+    #[test]
+    fn digest_labelled_with_the_wrong_algorithm_is_rejected() {
+        let sha256 = content_hash(HashAlgorithm::Sha256, b"abc");
+        for algorithm in [HashAlgorithm::Blake3, HashAlgorithm::Keccak256] {
+            let relabelled = ContentHash { algorithm, ..sha256 };
+            assert!(!matches_content_hash(&relabelled, b"abc"), "{:?}", algorithm);
+        }
+    }
+}
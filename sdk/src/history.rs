@@ -0,0 +1,272 @@
+//! Point-in-time reads of a medical record's content.
+
+use anchor_lang::prelude::Pubkey;
+use cyphermed::state::{ClinicalCode, ContentHash, MedicalRecord, RecordVersion, StorageLocator};
+
+use crate::error::{Result, SdkError};
+use crate::fetcher::AccountFetcher;
+use crate::pda::record_version_address;
+
+/// Content of a record as it was during one version
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordSnapshot {
+    pub version: u32,
+    pub data_hash: ContentHash,
+    /// Data-key epoch the content was encrypted under
+    pub key_epoch: u32,
+    /// Provider key that attested this version (`None` if unattested)
+    pub attested_by: Option<Pubkey>,
+    pub storage: Option<StorageLocator>,
+    pub metadata: Option<String>,
+    pub clinical_codes: Vec<ClinicalCode>,
+    /// When this version became current
+    pub valid_from: i64,
+    /// When this version was replaced (`None` for the current version)
+    pub valid_until: Option<i64>,
+    /// Who replaced this version (`None` for the current version)
+    pub superseded_by: Option<Pubkey>,
+}
+
+impl RecordSnapshot {
+    fn current(record: &MedicalRecord) -> Self {
+        Self {
+            version: record.version,
+            data_hash: record.data_hash,
+            key_epoch: record.key_epoch,
+            attested_by: record.attested_by,
+            storage: record.storage.clone(),
+            metadata: record.metadata.clone(),
+            clinical_codes: record.clinical_codes.clone(),
+            valid_from: record.content_updated_at,
+            valid_until: None,
+            superseded_by: None,
+        }
+    }
+}
+
+impl From<RecordVersion> for RecordSnapshot {
+    fn from(v: RecordVersion) -> Self {
+        Self {
+            version: v.version,
+            data_hash: v.data_hash,
+            key_epoch: v.key_epoch,
+            attested_by: v.attested_by,
+            storage: v.storage,
+            metadata: v.metadata,
+            clinical_codes: v.clinical_codes,
+            valid_from: v.valid_from,
+            valid_until: Some(v.superseded_at),
+            superseded_by: Some(v.updated_by),
+        }
+    }
+}
+
+/// Fetch a record's content as of `version`
+pub fn record_at_version<F: AccountFetcher>(
+    fetcher: &F,
+    record_address: &Pubkey,
+    version: u32,
+) -> Result<RecordSnapshot> {
+    let record: MedicalRecord = fetcher.fetch(record_address)?;
+    if version == 0 || version > record.version {
+        return Err(SdkError::VersionNotFound {
+            requested: version,
+            latest: record.version,
+        });
+    }
+
+    if version == record.version {
+        return Ok(RecordSnapshot::current(&record));
+    }
+
+    let snapshot: RecordVersion = fetcher.fetch(&record_version_address(record_address, version))?;
+    Ok(snapshot.into())
+}
+
+/// Fetch a record's content as it was at `timestamp`
+///
+/// Binary-searches the version snapshots, so only `O(log n)` accounts are
+/// fetched for a record with `n` versions.
+pub fn record_at_timestamp<F: AccountFetcher>(
+    fetcher: &F,
+    record_address: &Pubkey,
+    timestamp: i64,
+) -> Result<RecordSnapshot> {
+    let record: MedicalRecord = fetcher.fetch(record_address)?;
+    if timestamp < record.created_at {
+        return Err(SdkError::TimestampBeforeCreation(timestamp));
+    }
+
+    // Find the earliest superseded version still current at `timestamp`
+    let (mut low, mut high) = (1u32, record.version);
+    let mut found = None;
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let snapshot: RecordVersion = fetcher.fetch(&record_version_address(record_address, mid))?;
+        if snapshot.superseded_at > timestamp {
+            high = mid;
+            found = Some(snapshot);
+        } else {
+            low = mid + 1;
+        }
+    }
+
+    // `found` always holds the snapshot for the final `high`, if it was ever moved
+    match found {
+        Some(snapshot) => Ok(snapshot.into()),
+        None => Ok(RecordSnapshot::current(&record)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use anchor_lang::AccountSerialize;
+    use cyphermed::state::{HashAlgorithm, Role};
+
+    use super::*;
+
+    const CREATED_AT: i64 = 1_000;
+
+    fn address() -> Pubkey {
+        Pubkey::new_from_array([5; 32])
+    }
+
+    fn hash(version: u32) -> ContentHash {
+        ContentHash {
+            algorithm: HashAlgorithm::Sha256,
+            digest: [version as u8; 32],
+        }
+    }
+
+    fn serialize<T: AccountSerialize>(account: &T) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    /// A record at version 4, updated at 2000, 3000 and 4000, whose
+    /// `modified_at` was moved later by an attachment
+    fn fixture() -> HashMap<Pubkey, Vec<u8>> {
+        let latest = 4;
+        let record = MedicalRecord {
+            patient: Pubkey::new_from_array([1; 32]),
+            created_by: Pubkey::new_from_array([2; 32]),
+            created_by_role: Role::Hospital,
+            record_type: 0,
+            record_id: "rec-1".to_string(),
+            data_hash: hash(latest),
+            storage: None,
+            created_at: CREATED_AT,
+            modified_at: 9_000,
+            content_updated_at: 4_000,
+            last_accessed: 0,
+            access_count: 0,
+            is_active: true,
+            deleted_by: None,
+            deleted_at: None,
+            retention_flagged_at: None,
+            metadata: Some(format!("v{}", latest)),
+            version: latest,
+            amendment_count: 0,
+            attested: false,
+            attested_by: None,
+            key_epoch: 0,
+            needs_rekey: false,
+            attachment_count: 1,
+            next_attachment_index: 1,
+            clinical_codes: Vec::new(),
+            bump: 255,
+        };
+
+        let mut accounts = HashMap::new();
+        accounts.insert(address(), serialize(&record));
+        for version in 1..latest {
+            let snapshot = RecordVersion {
+                record: address(),
+                version,
+                data_hash: hash(version),
+                key_epoch: 0,
+                attested_by: None,
+                storage: None,
+                metadata: Some(format!("v{}", version)),
+                clinical_codes: Vec::new(),
+                valid_from: if version == 1 { CREATED_AT } else { version as i64 * 1_000 },
+                superseded_at: (version as i64 + 1) * 1_000,
+                updated_by: Pubkey::new_from_array([3; 32]),
+                update_note: String::new(),
+                bump: 255,
+            };
+            accounts.insert(record_version_address(&address(), version), serialize(&snapshot));
+        }
+        accounts
+    }
+
+    #[test]
+    fn record_at_version_reads_snapshots_and_the_live_record() {
+        let accounts = fixture();
+
+        let first = record_at_version(&accounts, &address(), 1).unwrap();
+        assert_eq!(first.data_hash, hash(1));
+        assert_eq!(first.valid_from, CREATED_AT);
+        assert_eq!(first.valid_until, Some(2_000));
+
+        let latest = record_at_version(&accounts, &address(), 4).unwrap();
+        assert_eq!(latest.data_hash, hash(4));
+        assert_eq!(latest.valid_from, 4_000);
+        assert_eq!(latest.valid_until, None);
+        assert_eq!(latest.superseded_by, None);
+    }
+
+    #[test]
+    fn record_at_version_rejects_unknown_versions() {
+        let accounts = fixture();
+        for version in [0, 5] {
+            assert!(matches!(
+                record_at_version(&accounts, &address(), version),
+                Err(SdkError::VersionNotFound { requested, latest: 4 }) if requested == version
+            ));
+        }
+    }
+
+    #[test]
+    fn record_at_timestamp_picks_the_version_current_then() {
+        let accounts = fixture();
+        let cases = [
+            (CREATED_AT, 1),
+            (1_999, 1),
+            (2_000, 2),
+            (2_500, 2),
+            (3_999, 3),
+            (4_000, 4),
+            (100_000, 4),
+        ];
+        for (timestamp, version) in cases {
+            let snapshot = record_at_timestamp(&accounts, &address(), timestamp).unwrap();
+            assert_eq!(snapshot.version, version, "at {}", timestamp);
+            assert_eq!(snapshot.data_hash, hash(version));
+            assert!(snapshot.valid_from <= timestamp);
+            assert!(snapshot.valid_until.is_none_or(|until| timestamp < until));
+        }
+    }
+
+    #[test]
+    fn record_at_timestamp_rejects_times_before_creation() {
+        let accounts = fixture();
+        assert!(matches!(
+            record_at_timestamp(&accounts, &address(), CREATED_AT - 1),
+            Err(SdkError::TimestampBeforeCreation(t)) if t == CREATED_AT - 1
+        ));
+    }
+
+    #[test]
+    fn missing_snapshots_are_reported() {
+        let mut accounts = fixture();
+        accounts.remove(&record_version_address(&address(), 2));
+        assert!(matches!(
+            record_at_version(&accounts, &address(), 2),
+            Err(SdkError::AccountNotFound(_))
+        ));
+    }
+}
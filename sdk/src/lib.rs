@@ -0,0 +1,19 @@
+//! Client-side helpers for reading and verifying CypherMed accounts.
+//!
+//! The SDK never talks to an RPC node directly. Callers plug in an
+//! [`AccountFetcher`] backed by whatever client they already use.
+
+pub mod error;
+pub mod fetcher;
+pub mod pda;
+pub mod history;
+pub mod audit_chain;
+pub mod compressed_audit;
+pub mod hashing;
+pub mod storage;
+pub mod envelope;
+pub mod escrow;
+
+pub use error::SdkError;
+pub use fetcher::AccountFetcher;
+pub use storage::StorageResolver;
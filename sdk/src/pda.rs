@@ -0,0 +1,78 @@
+//! Program-derived address helpers mirroring the program's seeds.
+
+use anchor_lang::prelude::Pubkey;
+
+/// Patient account owned by `authority`
+pub fn patient_address(authority: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"patient", authority.as_ref()], &cyphermed::ID).0
+}
+
+/// Medical record `record_id` of `patient`
+pub fn record_address(patient: &Pubkey, record_id: &str) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"record", patient.as_ref(), record_id.as_bytes()],
+        &cyphermed::ID,
+    )
+    .0
+}
+
+/// Snapshot of `record` taken when `version` was superseded
+pub fn record_version_address(record: &Pubkey, version: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"record_version", record.as_ref(), &version.to_le_bytes()],
+        &cyphermed::ID,
+    )
+    .0
+}
+
+/// Audit entry number `sequence` in `patient`'s audit trail
+pub fn audit_log_address(patient: &Pubkey, sequence: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"audit", patient.as_ref(), &sequence.to_le_bytes()],
+        &cyphermed::ID,
+    )
+    .0
+}
+
+/// Ed25519 signing key registration of `provider`
+pub fn signing_key_address(provider: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"signing_key", provider.as_ref()], &cyphermed::ID).0
+}
+
+/// Attachment number `index` of `record`
+pub fn attachment_address(record: &Pubkey, index: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"attachment", record.as_ref(), &index.to_le_bytes()],
+        &cyphermed::ID,
+    )
+    .0
+}
+
+/// X25519 encryption key registration of `owner`
+pub fn encryption_key_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"encryption_key", owner.as_ref()], &cyphermed::ID).0
+}
+
+/// Envelope carrying `patient`'s data key to `grantee`
+pub fn key_envelope_address(patient: &Pubkey, grantee: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"key_envelope", patient.as_ref(), grantee.as_ref()],
+        &cyphermed::ID,
+    )
+    .0
+}
+
+/// Progress of `patient`'s data-key rotation
+pub fn key_rotation_address(patient: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"key_rotation", patient.as_ref()], &cyphermed::ID).0
+}
+
+/// Shamir escrow of `patient`'s emergency decryption key
+pub fn key_escrow_address(patient: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"key_escrow", patient.as_ref()], &cyphermed::ID).0
+}
+
+/// Registry entry for record type `code`
+pub fn record_type_definition_address(code: u16) -> Pubkey {
+    Pubkey::find_program_address(&[b"record_type", &code.to_le_bytes()], &cyphermed::ID).0
+}
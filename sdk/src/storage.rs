@@ -0,0 +1,168 @@
+//! Fetching the off-chain blobs a `StorageLocator` points at.
+//!
+//! Like [`AccountFetcher`](crate::AccountFetcher), the SDK does not ship
+//! network clients. Callers implement [`StorageResolver`] for each backend
+//! they use (an IPFS gateway, an S3 client, the PACS API...);
+//! [`LocalStorage`] keeps blobs on the local filesystem in place of remote
+//! storage for tests and offline tooling.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use cyphermed::state::{ContentHash, StorageLocator};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Result, SdkError};
+use crate::hashing::matches_content_hash;
+
+/// Source of off-chain blobs named by a `StorageLocator`
+pub trait StorageResolver {
+    /// Return the blob stored at `locator`
+    fn fetch_blob(&self, locator: &StorageLocator) -> Result<Vec<u8>>;
+
+    /// Fetch a blob and check it against the digest recorded on-chain
+    fn resolve(&self, locator: &StorageLocator, expected: &ContentHash) -> Result<Vec<u8>> {
+        let blob = self.fetch_blob(locator)?;
+        if !matches_content_hash(expected, &blob) {
+            return Err(SdkError::ContentHashMismatch(locator_uri(locator)));
+        }
+        Ok(blob)
+    }
+}
+
+/// URI form of a locator, for logs and error messages
+pub fn locator_uri(locator: &StorageLocator) -> String {
+    match locator {
+        StorageLocator::Ipfs { cid } => format!("ipfs://{cid}"),
+        StorageLocator::Arweave { tx_id } => format!("ar://{tx_id}"),
+        StorageLocator::Url { url } => url.clone(),
+        StorageLocator::Custom { scheme, reference } => format!("{scheme}://{reference}"),
+    }
+}
+
+/// Blobs kept under a local directory, one subdirectory per backend
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// File that holds the blob for `locator`
+    ///
+    /// Identifiers that are not safe file names (URLs, custom references)
+    /// are stored under the hex SHA-256 of the identifier.
+    pub fn path_for(&self, locator: &StorageLocator) -> PathBuf {
+        let (dir, id) = match locator {
+            StorageLocator::Ipfs { cid } => ("ipfs", cid.as_str()),
+            StorageLocator::Arweave { tx_id } => ("arweave", tx_id.as_str()),
+            StorageLocator::Url { url } => ("url", url.as_str()),
+            StorageLocator::Custom { scheme, reference } => (scheme.as_str(), reference.as_str()),
+        };
+        self.root.join(file_name(dir)).join(file_name(id))
+    }
+
+    /// Write `data` where `locator` will find it
+    pub fn store(&self, locator: &StorageLocator, data: &[u8]) -> Result<PathBuf> {
+        let path = self.path_for(locator);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| SdkError::Storage(e.to_string()))?;
+        }
+        fs::write(&path, data).map_err(|e| SdkError::Storage(e.to_string()))?;
+        Ok(path)
+    }
+}
+
+impl StorageResolver for LocalStorage {
+    fn fetch_blob(&self, locator: &StorageLocator) -> Result<Vec<u8>> {
+        fs::read(self.path_for(locator)).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => SdkError::BlobNotFound(locator_uri(locator)),
+            _ => SdkError::Storage(e.to_string()),
+        })
+    }
+}
+
+fn file_name(id: &str) -> String {
+    let safe = !id.is_empty()
+        && !id.starts_with('.')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if safe {
+        id.to_string()
+    } else {
+        Sha256::digest(id.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::content_hash;
+    use cyphermed::state::HashAlgorithm;
+
+    fn storage(name: &str) -> LocalStorage {
+        let root = std::env::temp_dir().join(format!(
+            "cyphermed-sdk-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&root);
+        LocalStorage::new(root)
+    }
+
+    fn locators() -> Vec<StorageLocator> {
+        vec![
+            StorageLocator::Ipfs {
+                cid: "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG".to_string(),
+            },
+            StorageLocator::Arweave {
+                tx_id: "bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U".to_string(),
+            },
+            StorageLocator::Url {
+                url: "https://records.example-hospital.org/blobs/42?v=1".to_string(),
+            },
+            StorageLocator::Custom {
+                scheme: "pacs".to_string(),
+                reference: "studies/1.2.840.113619/../series".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn resolves_and_hash_checks_every_backend() {
+        let storage = storage("resolve");
+        for (i, locator) in locators().iter().enumerate() {
+            let blob = format!("encrypted blob {i}").into_bytes();
+            let path = storage.store(locator, &blob).unwrap();
+            assert!(path.starts_with(&storage.root));
+
+            let expected = content_hash(HashAlgorithm::Sha256, &blob);
+            assert_eq!(storage.resolve(locator, &expected).unwrap(), blob);
+        }
+    }
+
+    #[test]
+    fn rejects_tampered_and_missing_blobs() {
+        let storage = storage("reject");
+        let locator = &locators()[2];
+        let expected = content_hash(HashAlgorithm::Blake3, b"original");
+
+        assert!(matches!(
+            storage.resolve(locator, &expected),
+            Err(SdkError::BlobNotFound(_))
+        ));
+
+        storage.store(locator, b"tampered").unwrap();
+        assert!(matches!(
+            storage.resolve(locator, &expected),
+            Err(SdkError::ContentHashMismatch(_))
+        ));
+    }
+}
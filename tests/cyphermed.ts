@@ -3,6 +3,7 @@ import { Program } from "@coral-xyz/anchor";
 import { Cyphermed } from "../target/types/cyphermed";
 import { PublicKey, Keypair, SystemProgram } from "@solana/web3.js";
 import { expect } from "chai";
+import { createHash } from "crypto";
 
 describe("CypherMed - Medical Records Protocol", () => {
   const provider = anchor.AnchorProvider.env();
@@ -10,6 +11,27 @@ describe("CypherMed - Medical Records Protocol", () => {
 
   const program = anchor.workspace.Cyphermed as Program<Cyphermed>;
 
+  // Patients in these suites use the default account audit mode
+  const noCompressedAudit = {
+    auditTree: null,
+    compressionProgram: null,
+    noopProgram: null,
+  };
+
+  // Audit entries are keyed by the patient's running audit sequence
+  const nextAuditPda = async (patient: PublicKey): Promise<PublicKey> => {
+    const patientAccount = await program.account.patient.fetch(patient);
+    const [auditPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("audit"),
+        patient.toBuffer(),
+        Buffer.from(patientAccount.auditCount.toArray("le", 8)),
+      ],
+      program.programId
+    );
+    return auditPda;
+  };
+
   // Test accounts
   let patientKeypair: Keypair;
   let doctorKeypair: Keypair;
@@ -78,16 +100,23 @@ describe("CypherMed - Medical Records Protocol", () => {
     await program.methods
       .grantAccess(
         { doctor: {} },
-        [{ generalMedical: {} }, { prescription: {} }],
+        [0, 1],
         null,
         true,
         true,
         true,
-        "Regular treatment"
+        false,
+        "Regular treatment",
+        null,
+        null
       )
       .accounts({
         patient: patientPda,
+        auditLog: await nextAuditPda(patientPda),
+        compressedAudit: noCompressedAudit,
         accessGrant: accessGrantPda,
+        granteeEncryptionKey: null,
+        keyEnvelope: null,
         provider: doctorKeypair.publicKey,
         authority: patientKeypair.publicKey,
         systemProgram: SystemProgram.programId,
@@ -108,17 +137,7 @@ describe("CypherMed - Medical Records Protocol", () => {
       program.programId
     );
 
-    const patientAccount = await program.account.patient.fetch(patientPda);
-    const [auditLogPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("audit"),
-        recordPda.toBuffer(),
-        doctorKeypair.publicKey.toBuffer(),
-        Buffer.from("create"),
-        Buffer.from(patientAccount.recordCount.toArray("le", 8)),
-      ],
-      program.programId
-    );
+    const auditLogPda = await nextAuditPda(patientPda);
 
     const [accessGrantPda] = PublicKey.findProgramAddressSync(
       [
@@ -132,16 +151,35 @@ describe("CypherMed - Medical Records Protocol", () => {
     await program.methods
       .createRecord(
         recordId,
-        { generalMedical: {} },
-        "sha256_hash_123",
+        0,
+        {
+          algorithm: { sha256: {} },
+          digest: [...createHash("sha256").update("encrypted record").digest()],
+        },
         null,
-        "Initial consultation"
+        "Initial consultation",
+        []
       )
       .accounts({
         patient: patientPda,
         record: recordPda,
+        tombstone: PublicKey.findProgramAddressSync(
+          [Buffer.from("tombstone"), recordPda.toBuffer()],
+          program.programId
+        )[0],
         accessGrant: accessGrantPda,
+        recordTypeDefinition: PublicKey.findProgramAddressSync(
+          [Buffer.from("record_type"), Buffer.from([0, 0])],
+          program.programId
+        )[0],
+        config: null,
+        providerSigningKey: PublicKey.findProgramAddressSync(
+          [Buffer.from("signing_key"), doctorKeypair.publicKey.toBuffer()],
+          program.programId
+        )[0],
+        instructionsSysvar: null,
         auditLog: auditLogPda,
+        compressedAudit: noCompressedAudit,
         provider: doctorKeypair.publicKey,
         systemProgram: SystemProgram.programId,
       })